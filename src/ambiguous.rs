@@ -0,0 +1,100 @@
+/// Characters which are easily mistaken for one another when read aloud or from a printout.
+pub const AMBIGUOUS: &str = "Il1O0|;:,.`'\"";
+
+/// Whether the character is one of the [`AMBIGUOUS`] characters.
+pub fn is_ambiguous(char: char) -> bool {
+    AMBIGUOUS.contains(char)
+}
+
+/// Whether the text has an ambiguous character in either case, since a transformation can change the case of any letter.
+pub fn could_be_ambiguous(text: &str) -> bool {
+    text.chars().any(|c| is_ambiguous(c) || c.to_uppercase().any(is_ambiguous) || c.to_lowercase().any(is_ambiguous))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CharRules, Generator, Mode, RpgenError, Rules, WordList};
+
+    fn words() -> WordList {
+        WordList::from_words(["nut", "cart", "bend", "mill", "oak", "rim"])
+    }
+
+    fn generate(rules: Rules) -> Vec<String> {
+        let rules = Rules {
+            exclude_ambiguous: true,
+            amount: 50,
+            min_length: 3,
+            max_length: 4,
+            ..rules
+        };
+        let passwords = Generator::with_word_list(rules, words()).unwrap().generate().unwrap();
+        passwords.iter().map(|password| password.reveal().to_string()).collect()
+    }
+
+    #[test]
+    fn letters_are_ambiguous_in_either_case() {
+        assert!(is_ambiguous('l') && is_ambiguous('0') && is_ambiguous('|'));
+        assert!(!is_ambiguous('o') && !is_ambiguous('i'));
+        assert!(could_be_ambiguous("oak") && could_be_ambiguous("rim"));
+        assert!(!could_be_ambiguous("nut"));
+    }
+
+    #[test]
+    fn words_leave_out_ambiguous_words_and_characters() {
+        for password in generate(Rules::default()) {
+            assert!(!password.chars().any(is_ambiguous), "{}", password);
+        }
+        assert_eq!(words().without_ambiguous().iter().collect::<Vec<_>>(), ["nut", "cart", "bend"]);
+    }
+
+    #[test]
+    fn chars_leave_out_ambiguous_characters() {
+        for password in generate(Rules { mode: Mode::Chars, ..Rules::default() }) {
+            assert!(!password.chars().any(is_ambiguous), "{}", password);
+        }
+    }
+
+    #[test]
+    fn patterns_leave_out_ambiguous_characters() {
+        let rules = Rules {
+            pattern: Some(Box::from("{WORD}{sep}{d:4}{sym:3}{word}")),
+            separator_alphabet: Box::from("-.,;_"),
+            ..Rules::default()
+        };
+        for password in generate(rules) {
+            assert!(!password.chars().any(is_ambiguous), "{}", password);
+        }
+    }
+
+    #[test]
+    fn pools_left_empty_are_rejected() {
+        let rules = Rules {
+            exclude_ambiguous: true,
+            min_length: 3,
+            max_length: 4,
+            ..Rules::default()
+        };
+        let words = WordList::from_words(["mill", "oak", "rim"]);
+        assert!(matches!(Generator::with_word_list(rules.clone(), words), Err(RpgenError::Invalid(_))));
+
+        let separators = Rules {
+            separator_alphabet: Box::from(".,;"),
+            ..rules.clone()
+        };
+        assert_eq!(separators.validate()[0].field, "separator_alphabet");
+
+        let chars = CharRules {
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbols: String::new(),
+            custom: "0O1lI".to_string(),
+            min_symbols: 0,
+            ..CharRules::default()
+        };
+        let rules = Rules { mode: Mode::Chars, chars, ..rules };
+        let fields = rules.validate().into_iter().map(|problem| problem.field).collect::<Vec<_>>();
+        assert!(fields.contains(&"chars".to_string()), "{:?}", fields);
+    }
+}
@@ -0,0 +1,114 @@
+/// Replaces every accented letter with its plain ASCII spelling, like `é` to `e` and `ü` to `ue`.
+///
+/// Returns nothing if the text has a character which has no ASCII spelling.
+pub fn transliterate(text: &str) -> Option<String> {
+    let mut ascii = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            c if c.is_ascii() => ascii.push(c),
+            c => ascii.push_str(spelling(c)?),
+        }
+    }
+
+    Some(ascii)
+}
+
+/// Whether the character can be typed on any keyboard, which is printable ASCII or a space.
+pub fn is_keyboard_safe(char: char) -> bool {
+    char == ' ' || char.is_ascii_graphic()
+}
+
+fn spelling(char: char) -> Option<&'static str> {
+    let spelling = match char {
+        'ä' | 'æ' => "ae",
+        'ö' | 'ø' | 'œ' => "oe",
+        'ü' => "ue",
+        'ß' => "ss",
+        'Ä' | 'Æ' => "Ae",
+        'Ö' | 'Ø' | 'Œ' => "Oe",
+        'Ü' => "Ue",
+        'à' | 'á' | 'â' | 'ã' | 'å' => "a",
+        'ç' => "c",
+        'è' | 'é' | 'ê' | 'ë' => "e",
+        'ì' | 'í' | 'î' | 'ï' => "i",
+        'ñ' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' => "o",
+        'ù' | 'ú' | 'û' => "u",
+        'ý' | 'ÿ' => "y",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Å' => "A",
+        'Ç' => "C",
+        'È' | 'É' | 'Ê' | 'Ë' => "E",
+        'Ì' | 'Í' | 'Î' | 'Ï' => "I",
+        'Ñ' => "N",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' => "O",
+        'Ù' | 'Ú' | 'Û' => "U",
+        'Ý' => "Y",
+        _ => return None,
+    };
+
+    Some(spelling)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{Generator, Rules, Transformation};
+
+    fn generate(rules: Rules) -> Vec<String> {
+        let rules = Rules {
+            ascii_only: true,
+            amount: 200,
+            ..rules
+        };
+        let passwords = Generator::with_rng(rules, StdRng::seed_from_u64(3)).unwrap().generate().unwrap();
+        passwords.iter().map(|password| password.reveal().to_string()).collect()
+    }
+
+    #[test]
+    fn accents_are_spelled_out() {
+        assert_eq!(transliterate("Straße").as_deref(), Some("Strasse"));
+        assert_eq!(transliterate("Über").as_deref(), Some("Ueber"));
+        assert_eq!(transliterate("cœur").as_deref(), Some("coeur"));
+        assert_eq!(transliterate("ça-va").as_deref(), Some("ca-va"));
+        assert_eq!(transliterate("łódź"), None);
+    }
+
+    #[test]
+    fn only_printable_ascii_is_keyboard_safe() {
+        assert!(is_keyboard_safe(' ') && is_keyboard_safe('~') && is_keyboard_safe('a'));
+        assert!(!is_keyboard_safe('\t') && !is_keyboard_safe('\u{7f}') && !is_keyboard_safe('é'));
+    }
+
+    #[test]
+    fn passwords_stay_printable_ascii() {
+        for language in ["de", "fr"] {
+            for transliterate in [true, false] {
+                let rules = Rules {
+                    language: Box::from(language),
+                    transliterate,
+                    transform: Transformation::RANDOM,
+                    digits_before: 2,
+                    ..Rules::default()
+                };
+                for password in generate(rules) {
+                    assert!(password.chars().all(is_keyboard_safe), "{}", password);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn patterns_stay_printable_ascii() {
+        let rules = Rules {
+            language: Box::from("fr"),
+            pattern: Some(Box::from("{WORD}{sep}{word:6-9}{d:2}{sym:4}")),
+            ..Rules::default()
+        };
+        for password in generate(rules) {
+            assert!(password.chars().all(is_keyboard_safe), "{}", password);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ambiguous;
+use crate::error::Problem;
+
+/// The rules for [`Mode::Chars`](crate::mode::Mode::Chars) passwords made of random characters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CharRules {
+    /// The number of characters in each password.
+    pub length: usize,
+    /// Whether lowercase ASCII letters are used.
+    pub lowercase: bool,
+    /// Whether uppercase ASCII letters are used.
+    pub uppercase: bool,
+    /// Whether digits are used.
+    pub digits: bool,
+    /// The symbols which are used, empty to use none.
+    pub symbols: String,
+    /// Any other characters which are used.
+    pub custom: String,
+    /// The minimum number of lowercase letters in each password.
+    pub min_lowercase: usize,
+    /// The minimum number of uppercase letters in each password.
+    pub min_uppercase: usize,
+    /// The minimum number of digits in each password.
+    pub min_digits: usize,
+    /// The minimum number of symbols in each password.
+    pub min_symbols: usize,
+    /// The minimum number of custom characters in each password.
+    pub min_custom: usize,
+}
+
+impl CharRules {
+    /// Each character class with its characters and the minimum number of them in each password.
+    ///
+    /// Disabled classes have no characters, and [ambiguous](crate::ambiguous::AMBIGUOUS) characters are left out if excluded.
+    pub fn classes(&self, exclude_ambiguous: bool) -> Vec<(&'static str, Vec<char>, usize)> {
+        let enabled = |enabled: bool, chars: std::ops::RangeInclusive<char>| match enabled {
+            true => chars.collect(),
+            false => Vec::new(),
+        };
+
+        let mut classes = vec![
+            ("lowercase", enabled(self.lowercase, 'a'..='z'), self.min_lowercase),
+            ("uppercase", enabled(self.uppercase, 'A'..='Z'), self.min_uppercase),
+            ("digits", enabled(self.digits, '0'..='9'), self.min_digits),
+            ("symbols", unique(&self.symbols), self.min_symbols),
+            ("custom", unique(&self.custom), self.min_custom),
+        ];
+        if exclude_ambiguous {
+            classes.iter_mut().for_each(|(_, chars, _)| chars.retain(|c| !ambiguous::is_ambiguous(*c)));
+        }
+
+        classes
+    }
+
+    /// Every character from every class, without duplicates.
+    pub fn pool(&self, exclude_ambiguous: bool) -> Vec<char> {
+        let mut pool = self.classes(exclude_ambiguous).into_iter().flat_map(|(_, chars, _)| chars).collect::<Vec<_>>();
+        pool.sort_unstable();
+        pool.dedup();
+        pool
+    }
+
+    /// Every problem which stops the classes from filling a password while meeting every minimum.
+    pub fn validate(&self, exclude_ambiguous: bool) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        if self.length < 1 || self.length > 1024 {
+            problems.push(Problem::new(
+                "chars.length",
+                format!("Chars length must be within bounds of 1 and 1024, received {}", self.length),
+                "Set it to a number from 1 to 1024",
+            ));
+        }
+
+        for (name, chars, min) in self.classes(exclude_ambiguous) {
+            if min > 0 && chars.is_empty() {
+                problems.push(Problem::new(
+                    format!("chars.min_{}", name),
+                    format!("Chars requires at least {} {} but that class has no characters", min, name),
+                    format!("Set it to 0 or give the {} class some characters", name),
+                ));
+            }
+        }
+
+        let minimum = self.classes(exclude_ambiguous).iter().map(|(_, _, min)| min).sum::<usize>();
+        if minimum > self.length {
+            problems.push(Problem::new(
+                "chars.length",
+                format!("Chars length must be at least the sum of every minimum, {} is less than {}", self.length, minimum),
+                format!("Set it to at least {} or lower the minimums", minimum),
+            ));
+        }
+
+        if self.pool(exclude_ambiguous).is_empty() {
+            problems.push(Problem::new(
+                "chars",
+                "Chars must have at least one character class with characters",
+                "Enable lowercase, uppercase or digits, or set some symbols or custom characters",
+            ));
+        }
+
+        problems
+    }
+}
+
+impl Default for CharRules {
+    fn default() -> Self {
+        CharRules {
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: "!@#$%^&*-_=+?".to_string(),
+            custom: String::new(),
+            min_lowercase: 1,
+            min_uppercase: 1,
+            min_digits: 1,
+            min_symbols: 1,
+            min_custom: 0,
+        }
+    }
+}
+
+fn unique(chars: &str) -> Vec<char> {
+    let mut chars = chars.chars().collect::<Vec<_>>();
+    chars.sort_unstable();
+    chars.dedup();
+    chars
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{Generator, Mode, Rules};
+
+    #[test]
+    fn every_class_appears_at_least_its_minimum() {
+        let chars = CharRules {
+            length: 12,
+            symbols: "#".to_string(),
+            custom: "é".to_string(),
+            min_lowercase: 2,
+            min_uppercase: 3,
+            min_digits: 4,
+            min_symbols: 1,
+            min_custom: 2,
+            ..CharRules::default()
+        };
+        let rules = Rules {
+            mode: Mode::Chars,
+            chars,
+            amount: 50,
+            ..Rules::default()
+        };
+
+        for password in Generator::with_rng(rules, StdRng::seed_from_u64(1)).unwrap().generate().unwrap() {
+            let password = password.reveal();
+            let count = |matches: fn(&char) -> bool| password.chars().filter(matches).count();
+            assert_eq!(password.chars().count(), 12);
+            assert!(count(char::is_ascii_lowercase) >= 2, "{}", password);
+            assert!(count(char::is_ascii_uppercase) >= 3, "{}", password);
+            assert!(count(char::is_ascii_digit) >= 4, "{}", password);
+            assert!(count(|c| *c == '#') >= 1, "{}", password);
+            assert!(count(|c| *c == 'é') >= 2, "{}", password);
+        }
+    }
+
+    #[test]
+    fn minimums_must_fit_in_the_length() {
+        let chars = CharRules {
+            length: 4,
+            min_digits: 2,
+            ..CharRules::default()
+        };
+        let problems = chars.validate(false);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].field, "chars.length");
+        assert!(problems[0].fix.contains("at least 5"));
+        assert!(CharRules { length: 5, ..chars }.validate(false).is_empty());
+    }
+
+    #[test]
+    fn minimums_need_characters_in_their_class() {
+        let chars = CharRules {
+            symbols: String::new(),
+            ..CharRules::default()
+        };
+        let fields = chars.validate(false).into_iter().map(|problem| problem.field).collect::<Vec<_>>();
+
+        assert_eq!(fields, ["chars.min_symbols"]);
+        assert!(CharRules { min_symbols: 0, ..chars }.validate(false).is_empty());
+    }
+
+    #[test]
+    fn excluding_ambiguous_characters_shrinks_the_pool() {
+        let chars = CharRules::default();
+        let pool = chars.pool(true);
+
+        for c in ['0', 'O', 'l', '1', 'I'] {
+            assert!(chars.pool(false).contains(&c));
+            assert!(!pool.contains(&c), "{}", c);
+        }
+        assert_eq!(chars.pool(false).len() - pool.len(), 5);
+    }
+}
@@ -0,0 +1,804 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml::Value;
+
+use crate::error::{Problem, RpgenError};
+use crate::rules::Rules;
+use crate::schema::rules_schema;
+
+/// The prefix of the environment variables which set rules.
+pub const ENV_PREFIX: &str = "RPGEN_";
+
+/// The environment variables with the [prefix](ENV_PREFIX) which don't set a rule.
+const ENV_RESERVED: [&str; 2] = ["CONFIG", "PROFILE"];
+
+/// The tables of arbitrary keys which a config layer replaces as a whole instead of merging into.
+const REPLACED_TABLES: [&str; 1] = ["leet_table"];
+
+/// The formats a config file can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// `.toml` files, and the default for files without a known extension.
+    Toml,
+    /// `.json` files.
+    Json,
+    /// `.yaml` and `.yml` files.
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// The format of a config file from its extension, or from its content when the extension isn't known like `PGen.conf`.
+    pub fn detect(path: &Path, content: &str) -> ConfigFormat {
+        match path.extension().and_then(|extension| extension.to_str()).map(str::to_lowercase).as_deref() {
+            Some("json") => ConfigFormat::Json,
+            Some("yaml" | "yml") => ConfigFormat::Yaml,
+            Some("toml") => ConfigFormat::Toml,
+            _ if content.trim_start().starts_with('{') => ConfigFormat::Json,
+            _ if toml::from_str::<Value>(content).is_err() && serde_yaml::from_str::<serde_yaml::Mapping>(content).is_ok() => ConfigFormat::Yaml,
+            _ => ConfigFormat::Toml,
+        }
+    }
+
+    /// Parses the content of a config file in this format.
+    pub fn parse(&self, content: &str) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let value = match self {
+            ConfigFormat::Toml => toml::from_str(content)?,
+            ConfigFormat::Json => serde_json::from_str(content)?,
+            ConfigFormat::Yaml => serde_yaml::from_str(content)?,
+        };
+
+        Ok(value)
+    }
+}
+
+/// Where the value of a rule came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The default value of the rule.
+    Default,
+    /// A config file.
+    File(PathBuf),
+    /// A profile in a config file.
+    Profile(PathBuf, String),
+    /// An environment variable.
+    Environment(String),
+    /// A command line argument.
+    Argument,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Default => f.write_str("default"),
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Profile(path, name) => write!(f, "{} [profiles.{}]", path.display(), name),
+            Source::Environment(name) => write!(f, "${}", name),
+            Source::Argument => f.write_str("command line"),
+        }
+    }
+}
+
+/// [`Rules`] built up from layers of config files and arguments, remembering which layer set each value.
+///
+/// Values are keyed by their dotted path like `policy.min_length`.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// The rules after every layer so far.
+    pub rules: Rules,
+    sources: BTreeMap<String, Source>,
+    problems: Vec<Problem>,
+}
+
+impl Config {
+    /// Merges the rules read from a TOML, JSON or YAML config file, optionally with one of its profiles on top, over the current rules.
+    ///
+    /// Only the values present in the file replace the current ones and are attributed to it,
+    /// tables like the `policy` are merged key by key but the `leet_table` is replaced as a whole.
+    /// The rules are either at the top level of the file or in its `[default]` section, but not both,
+    /// and each `[profiles.<name>]` section only needs the values which differ from them.
+    /// Keys which aren't rules or whose values have the wrong type are skipped and kept as [problems](Config::validate),
+    /// but the rules themselves aren't checked, since a later layer can still change them.
+    pub fn load_file(&mut self, path: &Path, profile: Option<&str>) -> Result<(), RpgenError> {
+        let (mut base, mut profiles) = read_sections(path)?;
+        let mut profile = match profile {
+            Some(name) => match profiles.remove(name) {
+                Some(profile) => Some((name, profile)),
+                None => {
+                    return Err(RpgenError::validation(
+                        "profile",
+                        format!("Unknown profile {} in {}, expected one of {:?}", name, path.display(), profiles.keys().collect::<Vec<_>>()),
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        let mut value = Value::try_from(&self.rules).map_err(|err| RpgenError::validation("rules", format!("Couldn't serialize the rules: {}", err)))?;
+        let problems = check_layer(&value, &mut base, &Source::File(path.to_path_buf()));
+        self.problems.extend(problems);
+        merge(&mut value, base.clone());
+        if let Some((name, profile)) = &mut profile {
+            let problems = check_layer(&value, profile, &Source::Profile(path.to_path_buf(), name.to_string()));
+            self.problems.extend(problems.into_iter().map(|problem| problem.within(&format!("profiles.{}", name))));
+            merge(&mut value, profile.clone());
+        }
+        self.rules = value.try_into::<Rules>().map_err(|err| RpgenError::Parse {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        })?;
+
+        for key in REPLACED_TABLES.iter().filter(|key| base.get(key).is_some()) {
+            self.set_source(key, Source::File(path.to_path_buf()));
+        }
+        for (key, _) in flatten(&base) {
+            self.set_source(&key, Source::File(path.to_path_buf()));
+        }
+        if let Some((name, profile)) = &profile {
+            for key in REPLACED_TABLES.iter().filter(|key| profile.get(key).is_some()) {
+                self.set_source(key, Source::Profile(path.to_path_buf(), name.to_string()));
+            }
+            for (key, _) in flatten(profile) {
+                self.set_source(&key, Source::Profile(path.to_path_buf(), name.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies every `RPGEN_*` environment variable over the rules, like `RPGEN_WORDS=4` or `RPGEN_POLICY__MIN_LENGTH=12`
+    /// where a double underscore separates the keys of a table.
+    ///
+    /// Values are read as the type of the rule they set, lists like the pronounceable tables are comma separated.
+    /// Like the keys of a config file, variables which don't set a rule or can't be read as its type are skipped
+    /// and kept as [problems](Config::validate).
+    /// `RPGEN_CONFIG` and `RPGEN_PROFILE` choose the config file and profile instead, so they are skipped.
+    pub fn load_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) -> Result<(), RpgenError> {
+        let mut value = Value::try_from(&self.rules).map_err(|err| RpgenError::validation("rules", format!("Couldn't serialize the rules: {}", err)))?;
+        let schema = rules_schema();
+        let mut set = Vec::new();
+        for (name, raw) in vars {
+            let key = match name.strip_prefix(ENV_PREFIX) {
+                Some(key) if !ENV_RESERVED.contains(&key) => key.to_lowercase(),
+                _ => continue,
+            };
+            let path = key.split("__").map(str::to_string).collect::<Vec<_>>();
+            let keys = path.iter().map(String::as_str).collect::<Vec<_>>();
+            let source = Source::Environment(name.clone());
+
+            // Unknown rules are read as strings, so they are reported by the checks of the layer like any other unknown key.
+            let mut placeholders = value.clone();
+            let parsed = match lookup(&mut placeholders, &keys, &schema) {
+                Some((current, kind)) => parse_env(&name, &path, current, kind, &raw),
+                None => Ok(Value::String(raw)),
+            };
+            let mut layer = match parsed {
+                Ok(parsed) => nest(&path, parsed),
+                Err(problem) => {
+                    self.problems.push(problem);
+                    continue;
+                }
+            };
+
+            let problems = check_layer(&value, &mut layer, &source);
+            match problems.is_empty() {
+                true => {
+                    merge(&mut value, layer);
+                    set.push((path.iter().map(|key| quote(key)).collect::<Vec<_>>().join("."), name));
+                }
+                false => self.problems.extend(problems),
+            }
+        }
+
+        self.rules = value
+            .try_into::<Rules>()
+            .map_err(|err| RpgenError::validation("environment", format!("Couldn't apply the environment variables: {}", err)))?;
+        for (key, name) in set {
+            self.set_source(&key, Source::Environment(name));
+        }
+
+        Ok(())
+    }
+
+    /// The problems with the keys of every layer so far, which were skipped instead of applied.
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+
+    /// Every problem with the layers so far and with the rules they add up to.
+    pub fn validate(&self) -> Vec<Problem> {
+        self.problems.iter().cloned().chain(self.rules.validate()).collect()
+    }
+
+    /// Checks the layers and the rules they add up to, failing with every [problem](Config::validate) at once.
+    pub fn sanity_checks(&self) -> Result<(), RpgenError> {
+        RpgenError::check(self.validate())
+    }
+
+    /// The names of every profile in a config file.
+    pub fn profiles(path: &Path) -> Result<Vec<String>, RpgenError> {
+        read_sections(path).map(|(_, profiles)| profiles.into_keys().collect())
+    }
+
+    /// Attributes the value at the dotted path to the source, along with every value nested under it.
+    pub fn set_source(&mut self, key: &str, source: Source) {
+        let nested = format!("{}.", key);
+        for (_, value) in self.sources.iter_mut().filter(|(k, _)| *k == key || k.starts_with(&nested)) {
+            *value = source.clone();
+        }
+        if !self.sources.contains_key(key) && !self.sources.keys().any(|k| k.starts_with(&nested)) {
+            self.sources.insert(key.to_string(), source);
+        }
+    }
+
+    /// Where the value at the dotted path came from.
+    pub fn source(&self, key: &str) -> &Source {
+        self.sources.get(key).unwrap_or(&Source::Default)
+    }
+
+    /// The rules as TOML with every value annotated with where it came from.
+    pub fn annotated(&self) -> Result<String, RpgenError> {
+        let string = toml::to_string(&self.rules).map_err(|err| RpgenError::validation("rules", format!("Couldn't serialize the rules: {}", err)))?;
+        // Long values like the pronounceable tables aren't padded so the rest of the comments stay close.
+        let width = string.lines().map(|line| line.chars().count()).filter(|length| *length <= 40).max().unwrap_or_default();
+
+        let mut table = String::new();
+        let mut annotated = String::new();
+        for line in string.lines() {
+            match line.split_once(" = ") {
+                _ if line.starts_with('[') => {
+                    table = line.trim_matches(|c| c == '[' || c == ']').to_string();
+                    annotated.push_str(line);
+                }
+                Some((key, _)) => {
+                    let key = match table.is_empty() {
+                        true => key.to_string(),
+                        false => format!("{}.{}", table, key),
+                    };
+                    annotated.push_str(&format!("{:width$}  # {}", line, self.source(&key), width = width));
+                }
+                None => annotated.push_str(line),
+            }
+            annotated.push('\n');
+        }
+
+        Ok(annotated)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let rules = Rules::default();
+        let sources = flatten_rules(&rules).into_iter().map(|(key, _)| (key, Source::Default)).collect();
+
+        Config {
+            rules,
+            sources,
+            problems: Vec::new(),
+        }
+    }
+}
+
+/// The value at the path, creating any missing tables along the way and a missing value as a placeholder of the type the
+/// [schema](rules_schema) gives it, so rules which are unset by default like `policy.min_length` are still parsed as their type.
+///
+/// The schema of the value is returned along with it.
+fn lookup<'a, 's>(value: &'a mut Value, path: &[&str], schema: &'s serde_json::Value) -> Option<(&'a mut Value, &'s serde_json::Value)> {
+    match path.split_first() {
+        None => Some((value, schema)),
+        Some((key, rest)) => {
+            let schema = property(schema, key)?;
+            let table = value.as_table_mut()?;
+            let default = match schema.get("type").and_then(serde_json::Value::as_str) {
+                Some("integer") => Value::Integer(0),
+                Some("number") => Value::Float(0.0),
+                Some("boolean") => Value::Boolean(false),
+                Some("array") => Value::Array(Vec::new()),
+                Some("object") => Value::Table(Default::default()),
+                _ => Value::String(String::new()),
+            };
+            lookup(table.entry(key.to_string()).or_insert(default), rest, schema)
+        }
+    }
+}
+
+/// The schema of the key within a table of rules, or none if the table has no such rule.
+fn property<'s>(schema: &'s serde_json::Value, key: &str) -> Option<&'s serde_json::Value> {
+    schema
+        .get("properties")
+        .and_then(|properties| properties.get(key))
+        .or_else(|| schema.get("additionalProperties"))
+        .filter(|schema| schema.is_object())
+}
+
+/// Checks every key the layer sets against the rules it's merged over, removing the keys which aren't rules
+/// or whose values don't have the type of the rule so the rest of the layer can still be applied.
+fn check_layer(rules: &Value, layer: &mut Value, source: &Source) -> Vec<Problem> {
+    let schema = rules_schema();
+    let mut problems = Vec::new();
+    for (path, schema) in keys(layer, Some(&schema), Vec::new()) {
+        let field = path.iter().map(|key| quote(key)).collect::<Vec<_>>().join(".");
+        let problem = match schema {
+            None => {
+                let known = parent_keys(&path);
+                // Environment variables are suggested by their name instead of the key they set.
+                let rename = |key: &str| match source {
+                    Source::Environment(_) => {
+                        let path = [&path[..path.len() - 1], &[key.to_string()]].concat();
+                        format!("{}{}", ENV_PREFIX, path.join("__").to_uppercase())
+                    }
+                    _ => key.to_string(),
+                };
+                let fix = match (closest(path.last().unwrap(), &known), source) {
+                    (Some(key), _) => format!("Rename it to {} if that's what it should set", rename(key)),
+                    (None, Source::Environment(_)) => format!("Unset it or use one of {:?}", known.iter().map(|key| rename(key)).collect::<Vec<_>>()),
+                    (None, _) => format!("Remove it or use one of {:?}", known),
+                };
+                Some(Problem::new(field, format!("Unknown rule in {}", source), fix))
+            }
+            Some(schema) => {
+                let mut candidate = rules.clone();
+                merge(&mut candidate, nest(&path, get(layer, &path).clone()));
+                candidate.try_into::<Rules>().err().map(|err| {
+                    // The key is already the field of the problem.
+                    let err = err.to_string();
+                    let reason = err.split(" for key `").next().unwrap();
+                    Problem::new(field, format!("Invalid value in {}, {}", source, reason), expected(schema))
+                })
+            }
+        };
+        if let Some(problem) = problem {
+            remove(layer, &path);
+            problems.push(problem);
+        }
+    }
+
+    problems
+}
+
+/// The path to every value the layer sets along with the schema of the rule, which is none if the layer sets something that isn't a rule.
+///
+/// Tables of rules are followed down to the values in them, any other value is a single rule.
+fn keys<'s>(value: &Value, schema: Option<&'s serde_json::Value>, path: Vec<String>) -> Vec<(Vec<String>, Option<&'s serde_json::Value>)> {
+    match (value, schema) {
+        (Value::Table(table), Some(schema)) if schema.get("type").and_then(serde_json::Value::as_str) == Some("object") => table
+            .iter()
+            .flat_map(|(key, value)| keys(value, property(schema, key), [path.clone(), vec![key.clone()]].concat()))
+            .collect(),
+        _ => vec![(path, schema)],
+    }
+}
+
+/// The rules known in the table at the path.
+fn parent_keys(path: &[String]) -> Vec<String> {
+    let schema = rules_schema();
+    let parent = path[..path.len() - 1].iter().try_fold(&schema, |schema, key| property(schema, key));
+    match parent.and_then(|schema| schema.get("properties")).and_then(serde_json::Value::as_object) {
+        Some(properties) => properties.keys().cloned().collect(),
+        None => Vec::new(),
+    }
+}
+
+/// The known key which is a likely misspelling of the key, within two edits of it.
+fn closest<'a>(key: &str, known: &'a [String]) -> Option<&'a str> {
+    let distance = |a: &str, b: &str| {
+        let b = b.chars().collect::<Vec<_>>();
+        let mut previous = (0..=b.len()).collect::<Vec<_>>();
+        for (i, a) in a.chars().enumerate() {
+            let mut current = vec![i + 1];
+            for (j, b) in b.iter().enumerate() {
+                current.push((previous[j] + (a != *b) as usize).min(previous[j + 1] + 1).min(current[j] + 1));
+            }
+            previous = current;
+        }
+        previous[b.len()]
+    };
+
+    known.iter().map(|known| (distance(key, known), known)).filter(|(distance, _)| *distance <= 2).min().map(|(_, known)| known.as_str())
+}
+
+/// How to write a value of the type the schema describes, typed rules like the `transform` list what they accept.
+fn expected(schema: &serde_json::Value) -> String {
+    if let Some(values) = schema.get("enum").and_then(serde_json::Value::as_array) {
+        let values = values.iter().filter_map(serde_json::Value::as_str).collect::<Vec<_>>();
+        return format!("Set it to one of {}", values.join(", "));
+    }
+    if let (Some(_), Some(description)) = (schema.get("anyOf"), schema.get("description").and_then(serde_json::Value::as_str)) {
+        return format!("Set it to {}", description.trim_end_matches('.'));
+    }
+
+    match schema.get("type").and_then(serde_json::Value::as_str) {
+        Some("integer") => "Set it to a whole number".to_string(),
+        Some("number") => "Set it to a number".to_string(),
+        Some("boolean") => "Set it to true or false".to_string(),
+        Some("string") => "Set it to a string".to_string(),
+        Some("array") => "Set it to a list".to_string(),
+        Some("object") => "Set it to a table".to_string(),
+        _ => "Set it to a value of the right type".to_string(),
+    }
+}
+
+/// The value at the path.
+fn get<'a>(value: &'a Value, path: &[String]) -> &'a Value {
+    path.iter().fold(value, |value, key| &value[key.as_str()])
+}
+
+/// The value nested in tables along the path.
+fn nest(path: &[String], value: Value) -> Value {
+    path.iter().rev().fold(value, |value, key| Value::Table([(key.clone(), value)].into_iter().collect()))
+}
+
+/// Removes the value at the path, along with any table that's left empty by it.
+fn remove(value: &mut Value, path: &[String]) {
+    if let (Some(table), Some((key, rest))) = (value.as_table_mut(), path.split_first()) {
+        if !rest.is_empty() {
+            if let Some(nested) = table.get_mut(key) {
+                remove(nested, rest);
+                if !nested.as_table().is_some_and(|nested| nested.is_empty()) {
+                    return;
+                }
+            }
+        }
+        table.remove(key);
+    }
+}
+
+/// Parses the environment variable as the same type as the value it replaces,
+/// only rules which are always a list are split on commas, since a separator can also be written as a string.
+fn parse_env(name: &str, path: &[String], current: &Value, schema: &serde_json::Value, raw: &str) -> Result<Value, Problem> {
+    let invalid = |kind: &str, fix: &str| Problem::new(path.join("."), format!("{} must be {}, received {}", name, kind, raw), fix);
+    let value = match current {
+        Value::Integer(_) => Value::Integer(raw.trim().parse().map_err(|_| invalid("an integer", "Set it to a whole number"))?),
+        Value::Float(_) => Value::Float(raw.trim().parse().map_err(|_| invalid("a number", "Set it to a number"))?),
+        Value::Boolean(_) => Value::Boolean(raw.trim().to_lowercase().parse().map_err(|_| invalid("true or false", "Set it to true or false"))?),
+        Value::Array(_) if schema.get("type").and_then(serde_json::Value::as_str) == Some("array") => {
+            let items = raw.split(',').map(str::trim).filter(|item| !item.is_empty());
+            Value::Array(items.map(|item| Value::String(item.to_string())).collect())
+        }
+        Value::Table(_) => {
+            return Err(invalid("a single value", "Set the keys of the table with a double underscore like RPGEN_POLICY__MIN_LENGTH"));
+        }
+        _ => Value::String(raw.to_string()),
+    };
+
+    Ok(value)
+}
+
+/// Reads the rules and every profile from a config file.
+fn read_sections(path: &Path) -> Result<(Value, BTreeMap<String, Value>), RpgenError> {
+    let string = fs::read_to_string(path).map_err(|err| RpgenError::Io {
+        path: path.to_path_buf(),
+        source: err,
+    })?;
+    let mut value = ConfigFormat::detect(path, &string).parse(&string).map_err(|source| RpgenError::Parse {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let table = value.as_table_mut().ok_or_else(|| RpgenError::validation("rules", format!("{} must contain a table of rules", path.display())))?;
+    let profiles = match table.remove("profiles") {
+        None => BTreeMap::new(),
+        Some(Value::Table(profiles)) => profiles.into_iter().collect(),
+        Some(_) => return Err(RpgenError::validation("profiles", "Profiles must be a table of [profiles.<name>] sections")),
+    };
+    if let Some((name, _)) = profiles.iter().find(|(_, profile)| !profile.is_table()) {
+        return Err(RpgenError::validation(format!("profiles.{}", name), format!("Profile {} must be a [profiles.{}] section", name, name)));
+    }
+
+    let base = match table.remove("default") {
+        Some(default) if default.is_table() && !table.is_empty() => {
+            return Err(RpgenError::validation(
+                "default",
+                format!(
+                    "{} has rules both at the top level and in its [default] section, move {:?} into the [default] section",
+                    path.display(),
+                    table.keys().collect::<Vec<_>>()
+                ),
+            ))
+        }
+        Some(default) if default.is_table() => default,
+        Some(_) => return Err(RpgenError::validation("default", "Default must be a [default] section")),
+        None => value,
+    };
+
+    Ok((base, profiles))
+}
+
+/// Merges the values over the base, tables are merged key by key apart from the [replaced tables](REPLACED_TABLES)
+/// and anything else is replaced.
+fn merge(base: &mut Value, over: Value) {
+    match (base, over) {
+        (Value::Table(base), Value::Table(over)) => {
+            for (key, value) in over {
+                match base.get_mut(&key) {
+                    Some(existing) if !REPLACED_TABLES.contains(&key.as_str()) => merge(existing, value),
+                    _ => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
+
+fn flatten_rules(rules: &Rules) -> Vec<(String, Value)> {
+    Value::try_from(rules).map(|value| flatten(&value)).unwrap_or_default()
+}
+
+/// Every value which isn't a table, keyed by its dotted path.
+fn flatten(value: &Value) -> Vec<(String, Value)> {
+    let mut values = Vec::new();
+    if let Value::Table(table) = value {
+        for (key, value) in table {
+            let key = quote(key);
+            match value {
+                Value::Table(_) => values.extend(flatten(value).into_iter().map(|(nested, value)| (format!("{}.{}", key, nested), value))),
+                _ => values.push((key, value.clone())),
+            }
+        }
+    }
+
+    values
+}
+
+/// Quotes a key which isn't a valid bare TOML key.
+fn quote(key: &str) -> String {
+    match !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        true => key.to_string(),
+        false => format!("{:?}", key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::separator::Separator;
+    use crate::Transformation;
+
+    /// Writes a config file into a temporary directory unique to the test.
+    fn file(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rpgen-config-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Result<Config, RpgenError> {
+        let mut config = Config::default();
+        config.load_env(vars.iter().map(|(name, value)| (name.to_string(), value.to_string())))?;
+        Ok(config)
+    }
+
+    #[test]
+    fn env_sets_integers() {
+        let config = env(&[("RPGEN_WORDS", "4")]).unwrap();
+        assert_eq!(config.rules.words, 4);
+        assert_eq!(config.source("words"), &Source::Environment("RPGEN_WORDS".to_string()));
+    }
+
+    #[test]
+    fn env_sets_unset_optional_integers() {
+        let config = env(&[("RPGEN_POLICY__MIN_LENGTH", "16"), ("RPGEN_POLICY__MAX_LENGTH", "32"), ("RPGEN_POLICY__MAX_REPEATING", "2")]).unwrap();
+        assert_eq!(config.rules.policy.min_length, Some(16));
+        assert_eq!(config.rules.policy.max_length, Some(32));
+        assert_eq!(config.rules.policy.max_repeating, Some(2));
+        assert_eq!(config.source("policy.min_length"), &Source::Environment("RPGEN_POLICY__MIN_LENGTH".to_string()));
+    }
+
+    #[test]
+    fn env_sets_floats() {
+        assert_eq!(env(&[("RPGEN_LEET_PROBABILITY", "0.25")]).unwrap().rules.leet_probability, 0.25);
+    }
+
+    #[test]
+    fn env_sets_booleans() {
+        assert!(env(&[("RPGEN_ASCII_ONLY", "TRUE")]).unwrap().rules.ascii_only);
+        assert_eq!(env(&[("RPGEN_ASCII_ONLY", "yes")]).unwrap().problems()[0].field, "ascii_only");
+    }
+
+    #[test]
+    fn env_sets_strings() {
+        let config = env(&[("RPGEN_TRANSFORM", "UPPERCASE"), ("RPGEN_SEPARATOR_CHAR", "-"), ("RPGEN_LANGUAGE", "de")]).unwrap();
+        assert_eq!(config.rules.transform, Transformation::UPPERCASE);
+        assert_eq!(config.rules.separator_char, Separator::Fixed('-'));
+        assert_eq!(&*config.rules.language, "de");
+    }
+
+    #[test]
+    fn env_sets_unset_optional_strings() {
+        let config = env(&[("RPGEN_PATTERN", "1234"), ("RPGEN_WORDLIST", "eff-large")]).unwrap();
+        assert_eq!(config.rules.pattern.as_deref(), Some("1234"));
+        assert_eq!(config.rules.wordlist.as_deref(), Some("eff-large"));
+    }
+
+    #[test]
+    fn env_sets_a_separator_over_a_cycle() {
+        let mut config = Config::default();
+        config.rules.separator_char = Separator::Cycle(vec!['-', '_']);
+        config.load_env([("RPGEN_SEPARATOR_CHAR".to_string(), "RANDOM".to_string())]).unwrap();
+        assert_eq!(config.rules.separator_char, Separator::Random);
+    }
+
+    #[test]
+    fn env_sets_lists() {
+        let config = env(&[("RPGEN_PRONOUNCEABLE__VOWELS", "a, e,,o")]).unwrap();
+        assert_eq!(config.rules.pronounceable.vowels, vec!["a", "e", "o"]);
+    }
+
+    #[test]
+    fn env_sets_table_entries() {
+        let config = env(&[("RPGEN_LEET_TABLE__B", "8")]).unwrap();
+        assert_eq!(config.rules.leet_table.get("b").map(String::as_str), Some("8"));
+    }
+
+    #[test]
+    fn env_reports_unknown_and_mistyped_rules() {
+        let config = env(&[
+            ("RPGEN_WORD", "4"),
+            ("RPGEN_POLICY__MIN", "4"),
+            ("RPGEN_WORDS", "four"),
+            ("RPGEN_POLICY", "4"),
+            ("RPGEN_TRANSFORM", "SHOUT"),
+            ("RPGEN_LOG", "1"),
+            ("RPGEN_AMOUNT", "2"),
+        ])
+        .unwrap();
+
+        let fields = config.problems().iter().map(|problem| problem.field.as_str()).collect::<Vec<_>>();
+        assert_eq!(fields, ["word", "policy.min", "words", "policy", "transform", "log"]);
+        assert!(config.problems()[0].fix.contains("Rename it to RPGEN_WORDS"));
+        assert!(config.problems()[1].fix.contains("RPGEN_POLICY__MIN_LENGTH"));
+        assert!(config.problems()[5].fix.starts_with("Unset it"));
+        assert_eq!(config.rules.words, Rules::default().words);
+        assert_eq!(config.rules.amount, 2);
+        assert!(matches!(config.sanity_checks(), Err(RpgenError::Invalid(problems)) if problems.len() == 6));
+    }
+
+    #[test]
+    fn env_skips_reserved_and_unprefixed_variables() {
+        let config = env(&[("RPGEN_CONFIG", "PGen.conf"), ("RPGEN_PROFILE", "wifi"), ("WORDS", "9")]).unwrap();
+        assert_eq!(config.rules.words, Rules::default().words);
+    }
+
+    #[test]
+    fn file_merges_over_the_current_rules() {
+        let path = file("merge.toml", "words = 5\n[policy]\nrequire_digit = true\n");
+        let mut config = Config::default();
+        config.rules.digits_after = 1;
+        config.rules.policy.min_length = Some(12);
+        config.load_file(&path, None).unwrap();
+
+        assert_eq!(config.rules.words, 5);
+        assert_eq!(config.rules.digits_after, 1);
+        assert!(config.rules.policy.require_digit);
+        assert_eq!(config.rules.policy.min_length, Some(12));
+        assert_eq!(config.source("words"), &Source::File(path.clone()));
+        assert_eq!(config.source("digits_after"), &Source::Default);
+    }
+
+    #[test]
+    fn file_replaces_the_leet_table() {
+        let path = file("leet.toml", "[leet_table]\na = '@'\n");
+        let mut config = Config::default();
+        config.load_file(&path, None).unwrap();
+
+        assert_eq!(config.rules.leet_table.len(), 1);
+        assert_eq!(config.rules.leet_substitution('a'), Some("@"));
+        assert_eq!(config.rules.leet_substitution('e'), None);
+        assert_eq!(config.source("leet_table.a"), &Source::File(path));
+    }
+
+    #[test]
+    fn profile_merges_over_the_default_section() {
+        let path = file("profiles.toml", "[default]\nwords = 4\namount = 2\n[profiles.wifi]\nwords = 6\n[profiles.wifi.leet_table]\no = '0'\n");
+        let mut config = Config::default();
+        config.load_file(&path, Some("wifi")).unwrap();
+
+        assert_eq!(config.rules.words, 6);
+        assert_eq!(config.rules.amount, 2);
+        assert_eq!(config.rules.leet_table.keys().collect::<Vec<_>>(), vec!["o"]);
+        assert_eq!(config.source("words"), &Source::Profile(path.clone(), "wifi".to_string()));
+        assert_eq!(config.source("amount"), &Source::File(path.clone()));
+        assert_eq!(Config::profiles(&path).unwrap(), vec!["wifi"]);
+        assert!(Config::default().load_file(&path, Some("home")).is_err());
+    }
+
+    #[test]
+    fn file_rejects_rules_outside_the_default_section() {
+        let path = file("both.toml", "words = 5\n[default]\namount = 2\n");
+        assert!(matches!(Config::default().load_file(&path, None), Err(RpgenError::Validation { .. })));
+    }
+
+    #[test]
+    fn file_detects_json_and_yaml() {
+        let json = file("rules.json", r#"{ "words": 5, "policy": { "min_length": 20 } }"#);
+        let yaml = file("PGen.conf", "words: 6\npolicy:\n  min_length: 21\n");
+
+        let mut config = Config::default();
+        config.load_file(&json, None).unwrap();
+        assert_eq!((config.rules.words, config.rules.policy.min_length), (5, Some(20)));
+        config.load_file(&yaml, None).unwrap();
+        assert_eq!((config.rules.words, config.rules.policy.min_length), (6, Some(21)));
+    }
+
+    #[test]
+    fn file_reports_unknown_rules_and_skips_them() {
+        let path = file("unknown.toml", "wrods = 4\namount = 2\n[policy]\nmin_lenght = 30\n[polcy]\nmin_length = 30\n");
+        let mut config = Config::default();
+        config.load_file(&path, None).unwrap();
+
+        let fields = config.problems().iter().map(|problem| problem.field.as_str()).collect::<Vec<_>>();
+        assert_eq!(fields, ["polcy", "policy.min_lenght", "wrods"]);
+        assert!(config.problems()[2].fix.contains("Rename it to words"));
+        assert_eq!(config.rules.amount, 2);
+        assert_eq!(config.rules.policy, Rules::default().policy);
+        assert!(matches!(config.sanity_checks(), Err(RpgenError::Invalid(problems)) if problems.len() == 3));
+    }
+
+    #[test]
+    fn file_reports_mistyped_rules_with_the_other_problems() {
+        let path = file("mistyped.toml", "words = 0\ndigits_after = 'two'\namount = 0\n[policy]\nmin_length = 8\nrequire_digit = 1\n");
+        let mut config = Config::default();
+        config.load_file(&path, None).unwrap();
+
+        let problems = config.validate();
+        let fields = problems.iter().map(|problem| problem.field.as_str()).collect::<Vec<_>>();
+        assert_eq!(fields, ["digits_after", "policy.require_digit", "words", "amount"]);
+        assert_eq!(problems[0].fix, "Set it to a whole number");
+        assert!(!problems[0].reason.contains("for key"));
+        assert_eq!(config.rules.digits_after, Rules::default().digits_after);
+        assert_eq!(config.rules.policy.min_length, Some(8));
+    }
+
+    #[test]
+    fn file_reports_unknown_typed_values_with_the_other_problems() {
+        let path = file("typed.toml", "words = 0\ntransform = 'SHOUT'\nmode = 'emoji'\nseparator_char = 5\namount = 0\n");
+        let mut config = Config::default();
+        config.load_file(&path, None).unwrap();
+
+        let problems = config.validate();
+        let fields = problems.iter().map(|problem| problem.field.as_str()).collect::<Vec<_>>();
+        assert_eq!(fields, ["mode", "separator_char", "transform", "words", "amount"]);
+        assert!(problems[0].fix.starts_with("Set it to one of words, chars"));
+        assert!(problems[1].fix.starts_with("Set it to NONE, RANDOM, a single character"));
+        assert!(problems[2].reason.contains("unknown transformation SHOUT"));
+        assert!(problems[2].fix.starts_with("Set it to one of NONE, CAPITALISE"));
+        assert_eq!(config.rules.transform, Rules::default().transform);
+    }
+
+    #[test]
+    fn file_skipping_every_leet_entry_keeps_the_table() {
+        let path = file("leet-mistyped.toml", "[leet_table]\na = 4\n");
+        let mut config = Config::default();
+        config.load_file(&path, None).unwrap();
+
+        assert_eq!(config.problems().len(), 1);
+        assert_eq!(config.rules.leet_table, Rules::default().leet_table);
+    }
+
+    #[test]
+    fn profile_problems_name_the_profile() {
+        let path = file("profile-unknown.toml", "[profiles.wifi]\nwordz = 3\n");
+        let mut config = Config::default();
+        config.load_file(&path, Some("wifi")).unwrap();
+
+        assert_eq!(config.problems()[0].field, "profiles.wifi.wordz");
+        assert!(config.problems()[0].reason.contains("[profiles.wifi]"));
+    }
+
+    #[test]
+    fn env_layers_over_a_file() {
+        let path = file("layers.toml", "words = 5\namount = 2\n");
+        let mut config = Config::default();
+        config.load_file(&path, None).unwrap();
+        config.load_env([("RPGEN_WORDS".to_string(), "7".to_string())]).unwrap();
+
+        assert_eq!((config.rules.words, config.rules.amount), (7, 2));
+        assert_eq!(config.source("amount"), &Source::File(path));
+    }
+}
@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use crate::chars::CharRules;
+use crate::mode::Mode;
+use crate::pattern::{Pattern, Slot};
+use crate::pin::PinRules;
+use crate::rules::Rules;
+use crate::separator::Separator;
+use crate::wordlist::WordList;
+use crate::Transformation;
+
+/// The theoretical min-entropy of a password, in bits, broken down by where the randomness comes from.
+///
+/// Min-entropy is `-log2` of the chance of the single most likely password, so when some picks are more likely than others,
+/// like the words in a small length bucket, it is what an attacker guessing the most likely passwords first is up against.
+/// Each part is a lower bound for its own randomness, and their sum is a lower bound for the whole password
+/// as long as the share of passwords the policy accepts isn't overestimated.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Entropy {
+    /// Entropy from picking the length and then the word for each word slot.
+    pub words: f64,
+    /// Entropy added by the transformation, only non-zero for [`Transformation::RANDOM`] and [`Transformation::LEET_RANDOM`],
+    /// counted for the word which it adds the least to.
+    pub transformation: f64,
+    /// Entropy from picking random separator characters.
+    pub separators: f64,
+    /// Entropy from the random symbols of a [`Pattern`].
+    pub symbols: f64,
+    /// Entropy from the digits before and after the words, or from the whole PIN.
+    pub digits: f64,
+    /// Entropy from the random characters of [`Mode::Chars`].
+    pub characters: f64,
+    /// Entropy lost to the passwords the [`Policy`](crate::Policy) rejects, `log2` of the estimated share it accepts,
+    /// so it's zero without a policy and negative otherwise.
+    pub policy: f64,
+}
+
+impl Entropy {
+    /// The total entropy in bits.
+    pub fn bits(&self) -> f64 {
+        self.words + self.transformation + self.separators + self.symbols + self.digits + self.characters + self.policy
+    }
+
+    pub(crate) fn calculate(rules: &Rules, words: &WordList, pattern: Option<&Pattern>) -> Entropy {
+        match (rules.mode, pattern) {
+            (Mode::Chars, _) => Entropy::from_chars(&rules.chars, rules.exclude_ambiguous),
+            (Mode::Pin, _) => Entropy::from_pin(&rules.pin, &rules.digit_pool()),
+            (_, Some(pattern)) => Entropy::from_pattern(rules, words, pattern),
+            (_, None) => Entropy::from_rules(rules, words),
+        }
+    }
+
+    /// The entropy of picking one of the PINs which isn't rejected, counted exactly.
+    fn from_pin(pin: &PinRules, pool: &[char]) -> Entropy {
+        Entropy {
+            digits: (pin.accepted(pool).max(1) as f64).log2(),
+            ..Entropy::default()
+        }
+    }
+
+    /// The entropy of picking each character, not counting the extra entropy from shuffling the guaranteed characters.
+    fn from_chars(chars: &CharRules, exclude_ambiguous: bool) -> Entropy {
+        let mut characters = 0.0;
+        let mut guaranteed = 0;
+        for (_, class, min) in chars.classes(exclude_ambiguous).into_iter().filter(|(_, class, _)| !class.is_empty()) {
+            characters += (class.len() as f64).log2() * min as f64;
+            guaranteed += min;
+        }
+        characters += (chars.pool(exclude_ambiguous).len() as f64).log2() * chars.length.saturating_sub(guaranteed) as f64;
+
+        Entropy {
+            characters,
+            ..Entropy::default()
+        }
+    }
+
+    fn from_rules(rules: &Rules, words: &WordList) -> Entropy {
+        let (word, transformation) = match rules.mode {
+            Mode::Pronounceable => pronounceable(rules, rules.transform),
+            _ => word(rules, words, None, rules.transform),
+        };
+
+        let separator_count = rules.words.saturating_sub(1) + (rules.digits_before > 0) as usize + (rules.digits_after > 0) as usize;
+
+        Entropy {
+            words: word * rules.words as f64,
+            transformation: transformation * rules.words as f64,
+            separators: separators(rules, separator_count),
+            symbols: 0.0,
+            digits: (rules.digit_pool().len() as f64).log2() * (rules.digits_before + rules.digits_after) as f64,
+            ..Entropy::default()
+        }
+    }
+
+    fn from_pattern(rules: &Rules, words: &WordList, pattern: &Pattern) -> Entropy {
+        let mut entropy = Entropy::default();
+        let mut separator_count = 0;
+
+        for slot in &pattern.slots {
+            match slot {
+                Slot::Literal(_) => {}
+                Slot::Word { lengths, transformation } => {
+                    let (word, transformation) = match rules.mode {
+                        Mode::Pronounceable => pronounceable(rules, *transformation),
+                        _ => word(rules, words, *lengths, *transformation),
+                    };
+                    entropy.words += word;
+                    entropy.transformation += transformation;
+                }
+                Slot::Separator => separator_count += 1,
+                Slot::Digits(count) => entropy.digits += (rules.digit_pool().len() as f64).log2() * *count as f64,
+                Slot::Symbols(count) => entropy.symbols += min_entropy(&rules.separator_pool()) * *count as f64,
+            }
+        }
+
+        entropy.separators = separators(rules, separator_count);
+        entropy
+    }
+}
+
+impl Display for Entropy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Words:          {:.2} bits", self.words)?;
+        writeln!(f, "Transformation: {:.2} bits", self.transformation)?;
+        writeln!(f, "Separators:     {:.2} bits", self.separators)?;
+        writeln!(f, "Symbols:        {:.2} bits", self.symbols)?;
+        writeln!(f, "Digits:         {:.2} bits", self.digits)?;
+        writeln!(f, "Characters:     {:.2} bits", self.characters)?;
+        writeln!(f, "Policy:         {:.2} bits", self.policy)?;
+        write!(f, "Total:          {:.2} bits", self.bits())
+    }
+}
+
+/// The entropy of a single word and the least entropy the transformation adds to any of the words.
+fn word(rules: &Rules, words: &WordList, lengths: Option<(usize, usize)>, transformation: Transformation) -> (f64, f64) {
+    let transformation = |word: &str| match transformation {
+        Transformation::RANDOM => cased_chars(word) as f64,
+        Transformation::LEET_RANDOM => leet_chars(rules, word) as f64 * binary(rules.leet_probability),
+        _ => 0.0,
+    };
+
+    match lengths {
+        Some((min, max)) => bucketed(words, min, max, transformation),
+        None if rules.uniform_words => uniform(words, transformation),
+        None => bucketed(words, rules.min_length, rules.max_length, transformation),
+    }
+}
+
+/// The entropy of a single pronounceable word and the least entropy the transformation adds to it.
+///
+/// Different picks which happen to build the same word are counted as different words,
+/// and the most likely word has the fewest syllables with the most likely phonemes.
+fn pronounceable(rules: &Rules, transformation: Transformation) -> (f64, f64) {
+    let table = &rules.syllables();
+    let transformation = |phoneme: &str| match transformation {
+        Transformation::RANDOM => cased_chars(phoneme) as f64,
+        Transformation::LEET_RANDOM => leet_chars(rules, phoneme) as f64 * binary(rules.leet_probability),
+        _ => 0.0,
+    };
+    let least = |phonemes: &[String]| phonemes.iter().map(|p| transformation(p)).fold(f64::INFINITY, f64::min);
+
+    let coda = match table.coda_probability {
+        p if p <= 0.0 || table.codas.is_empty() => 1.0,
+        p if p >= 1.0 => most_likely(&table.codas),
+        p => (1.0 - p).max(p * most_likely(&table.codas)),
+    };
+    let syllable = -(most_likely(&table.onsets) * most_likely(&table.vowels) * coda).log2();
+    let mut transformed = least(&table.onsets) + least(&table.vowels);
+    if table.coda_probability >= 1.0 && !table.codas.is_empty() {
+        transformed += least(&table.codas);
+    }
+
+    let syllables = table.min_syllables as f64;
+    (((table.max_syllables - table.min_syllables + 1) as f64).log2() + syllable * syllables, transformed * syllables)
+}
+
+/// The entropy of the random separators, which only counts once if the same character is used for each separator.
+fn separators(rules: &Rules, count: usize) -> f64 {
+    match rules.separator_char {
+        Separator::Random if count > 0 => {
+            let per_char = min_entropy(&rules.separator_pool());
+            if rules.match_random_char {
+                per_char
+            } else {
+                per_char * count as f64
+            }
+        }
+        _ => 0.0,
+    }
+}
+
+/// Every word in the list is equally likely.
+fn uniform(words: &WordList, transformation: impl Fn(&str) -> f64) -> (f64, f64) {
+    let transformed = words.iter().map(transformation).fold(f64::INFINITY, f64::min);
+
+    ((words.len() as f64).log2(), finite(transformed))
+}
+
+/// Every length with words is equally likely, and every word within a length is equally likely,
+/// so the most likely words are those in the smallest bucket.
+fn bucketed(words: &WordList, min: usize, max: usize, transformation: impl Fn(&str) -> f64) -> (f64, f64) {
+    let lengths = words.lengths(min, max);
+    let smallest = lengths.iter().map(|length| words.bucket(*length).len()).min().unwrap_or_default();
+    let transformed = lengths.iter().flat_map(|length| words.bucket(*length)).map(|w| transformation(w)).fold(f64::INFINITY, f64::min);
+
+    (((lengths.len() * smallest).max(1) as f64).log2(), finite(transformed))
+}
+
+/// The number of characters which the random transformation can change the case of.
+fn cased_chars(word: &str) -> usize {
+    word.chars().filter(|c| c.is_uppercase() || c.is_lowercase()).count()
+}
+
+/// The number of characters which have a leet substitution.
+fn leet_chars(rules: &Rules, word: &str) -> usize {
+    word.chars().filter(|c| rules.leet_substitution(*c).is_some()).count()
+}
+
+/// The min-entropy of an event which happens with the probability.
+fn binary(probability: f64) -> f64 {
+    match probability {
+        p if p <= 0.0 || p >= 1.0 => 0.0,
+        p => -p.max(1.0 - p).log2(),
+    }
+}
+
+/// The min-entropy of picking a single character out of the alphabet, duplicates make a character more likely.
+fn min_entropy(alphabet: &str) -> f64 {
+    let mut counts = HashMap::new();
+    alphabet.chars().for_each(|c| *counts.entry(c).or_insert(0usize) += 1);
+    let total = alphabet.chars().count();
+
+    match counts.values().max() {
+        Some(&most) => -(most as f64 / total as f64).log2(),
+        None => 0.0,
+    }
+}
+
+/// The chance of picking the most common item out of the list, duplicates make an item more likely.
+fn most_likely(items: &[String]) -> f64 {
+    let mut counts = HashMap::new();
+    items.iter().for_each(|item| *counts.entry(item).or_insert(0usize) += 1);
+
+    counts.values().max().map_or(1.0, |&most| most as f64 / items.len() as f64)
+}
+
+/// Zero in place of the infinity left by taking the minimum of nothing.
+fn finite(bits: f64) -> f64 {
+    match bits.is_finite() {
+        true => bits,
+        false => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pronounceable::PronounceableRules;
+
+    const EPSILON: f64 = 1e-9;
+
+    /// Buckets of 2, 3 and 1 words for the lengths 3, 4 and 5.
+    fn words() -> WordList {
+        WordList::from_words(["cat", "dog", "bird", "fish", "frog", "horse"])
+    }
+
+    fn rules() -> Rules {
+        Rules {
+            words: 1,
+            min_length: 3,
+            max_length: 5,
+            transform: Transformation::NONE,
+            separator_char: Separator::None,
+            digits_after: 0,
+            ..Rules::default()
+        }
+    }
+
+    #[test]
+    fn bucketed_words_count_the_smallest_bucket() {
+        // Horse is picked a third of the time, while the Shannon entropy would be log2(3) + (1 + log2(3)) / 3.
+        assert!((bucketed(&words(), 3, 5, |_| 0.0).0 - 3f64.log2()).abs() < EPSILON);
+        // Cat and dog are each picked a quarter of the time.
+        assert!((bucketed(&words(), 3, 4, |_| 0.0).0 - 2.0).abs() < EPSILON);
+        assert!((bucketed(&words(), 4, 4, |_| 0.0).0 - 3f64.log2()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn uniform_words_count_every_word() {
+        assert!((uniform(&words(), |_| 0.0).0 - 6f64.log2()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn transformations_count_the_least_changed_word() {
+        assert_eq!(bucketed(&words(), 3, 5, |w| cased_chars(w) as f64).1, 3.0);
+        assert_eq!(uniform(&words(), |w| cased_chars(w) as f64).1, 3.0);
+    }
+
+    #[test]
+    fn duplicate_characters_lower_the_min_entropy() {
+        assert!((min_entropy("!!@") - (3.0 / 2.0f64).log2()).abs() < EPSILON);
+        assert!((min_entropy("!@$%") - 2.0).abs() < EPSILON);
+        assert_eq!(min_entropy(""), 0.0);
+    }
+
+    #[test]
+    fn binary_counts_the_more_likely_outcome() {
+        assert!((binary(0.3) + 0.7f64.log2()).abs() < EPSILON);
+        assert!((binary(0.5) - 1.0).abs() < EPSILON);
+        assert_eq!(binary(1.0), 0.0);
+    }
+
+    #[test]
+    fn rules_add_up_every_part() {
+        let rules = Rules {
+            words: 2,
+            separator_char: Separator::Random,
+            separator_alphabet: Box::from("!!@-"),
+            match_random_char: false,
+            digits_before: 1,
+            digits_after: 2,
+            ..rules()
+        };
+        let entropy = Entropy::calculate(&rules, &words(), None);
+
+        assert!((entropy.words - 2.0 * 3f64.log2()).abs() < EPSILON);
+        // Three separators between the digits and two words, each with a half chance of the most likely character.
+        assert!((entropy.separators - 3.0).abs() < EPSILON);
+        assert!((entropy.digits - 3.0 * 10f64.log2()).abs() < EPSILON);
+        assert_eq!(entropy.transformation, 0.0);
+    }
+
+    #[test]
+    fn matched_separators_count_once() {
+        let rules = Rules {
+            words: 3,
+            separator_char: Separator::Random,
+            separator_alphabet: Box::from("!@$%"),
+            match_random_char: true,
+            ..rules()
+        };
+
+        assert!((Entropy::calculate(&rules, &words(), None).separators - 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn patterns_count_each_slot() {
+        let rules = Rules {
+            separator_alphabet: Box::from("!@$%"),
+            ..rules()
+        };
+        let pattern = "{word:3-4}{sep}{d:2}{sym:3}".parse::<Pattern>().unwrap();
+        let entropy = Entropy::calculate(&rules, &words(), Some(&pattern));
+
+        assert!((entropy.words - 2.0).abs() < EPSILON);
+        assert!((entropy.digits - 2.0 * 10f64.log2()).abs() < EPSILON);
+        assert!((entropy.symbols - 6.0).abs() < EPSILON);
+        assert_eq!(entropy.separators, 0.0);
+    }
+
+    #[test]
+    fn pronounceable_counts_the_most_likely_syllables() {
+        let rules = Rules {
+            mode: Mode::Pronounceable,
+            pronounceable: PronounceableRules {
+                min_syllables: 2,
+                max_syllables: 3,
+                onsets: vec!["b".to_string(), "b".to_string(), "d".to_string()],
+                vowels: vec!["a".to_string(), "e".to_string()],
+                codas: vec!["n".to_string()],
+                coda_probability: 0.25,
+            },
+            ..rules()
+        };
+
+        // The most likely syllable is b, a and no coda with a chance of 2/3 * 1/2 * 3/4, which is 2 bits,
+        // and the most likely word is two of those after the one bit pick between two and three syllables.
+        assert!((Entropy::calculate(&rules, &WordList::default(), None).words - 5.0).abs() < EPSILON);
+    }
+}
@@ -0,0 +1,138 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can go wrong while loading rules or generating passwords.
+#[derive(Debug)]
+pub enum RpgenError {
+    /// A file couldn't be read, written or created.
+    Io { path: PathBuf, source: io::Error },
+    /// A config file couldn't be parsed.
+    Parse { path: PathBuf, source: Box<dyn Error + Send + Sync> },
+    /// A rule has an invalid value, the field is the path to the rule like `policy.min_length`.
+    Validation { field: String, reason: String },
+    /// The rules have one or more problems, found while [validating](crate::Rules::validate) them.
+    Invalid(Vec<Problem>),
+    /// A word list couldn't be loaded or has no usable words.
+    WordList(String),
+    /// The rng couldn't be seeded.
+    Rng(rand::Error),
+    /// No password meeting the policy could be generated.
+    Policy(String),
+}
+
+impl RpgenError {
+    pub(crate) fn validation(field: impl Into<String>, reason: impl Into<String>) -> RpgenError {
+        RpgenError::Validation {
+            field: field.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Fails with every problem, if there are any.
+    pub(crate) fn check(problems: Vec<Problem>) -> Result<(), RpgenError> {
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(RpgenError::Invalid(problems)),
+        }
+    }
+
+    /// The process exit code for this category of error, following `sysexits.h` where there is a match.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RpgenError::Parse { .. } => 65,
+            RpgenError::WordList(_) => 66,
+            RpgenError::Rng(_) => 71,
+            RpgenError::Io { .. } => 74,
+            RpgenError::Validation { .. } | RpgenError::Invalid(_) => 78,
+            RpgenError::Policy(_) => 79,
+        }
+    }
+}
+
+impl Display for RpgenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpgenError::Io { path, source } => write!(f, "Couldn't access {}: {}", path.display(), source),
+            RpgenError::Parse { path, source } => write!(f, "Couldn't parse {}: {}", path.display(), source),
+            RpgenError::Validation { field, reason } => write!(f, "{}: {}", field, reason),
+            RpgenError::Invalid(problems) if problems.len() == 1 => write!(f, "{}", problems[0]),
+            RpgenError::Invalid(problems) => {
+                write!(f, "Found {} problems with the rules:", problems.len())?;
+                problems.iter().try_for_each(|problem| write!(f, "\n    {}", problem))
+            }
+            RpgenError::WordList(reason) => f.write_str(reason),
+            RpgenError::Rng(source) => write!(f, "Couldn't seed the rng: {}", source),
+            RpgenError::Policy(reason) => f.write_str(reason),
+        }
+    }
+}
+
+impl Error for RpgenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RpgenError::Io { source, .. } => Some(source),
+            RpgenError::Parse { source, .. } => Some(source.as_ref()),
+            RpgenError::Rng(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// A single problem with a rule, found while [validating](crate::Rules::validate) the rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// The path to the rule like `policy.min_length`.
+    pub field: String,
+    /// What is wrong with the rule.
+    pub reason: String,
+    /// A suggestion for how to fix the rule.
+    pub fix: String,
+}
+
+impl Problem {
+    pub(crate) fn new(field: impl Into<String>, reason: impl Into<String>, fix: impl Into<String>) -> Problem {
+        Problem {
+            field: field.into(),
+            reason: reason.into(),
+            fix: fix.into(),
+        }
+    }
+
+    /// The same problem with the path of the table it was found in prepended to the field, like `profiles.wifi`.
+    pub fn within(self, table: &str) -> Problem {
+        Problem {
+            field: format!("{}.{}", table, self.field),
+            ..self
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}. {}.", self.field, self.reason, self.fix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation_names_the_field() {
+        let error = RpgenError::validation("words", "Couldn't parse abc as a whole number");
+        assert_eq!(error.to_string(), "words: Couldn't parse abc as a whole number");
+        assert_eq!(error.exit_code(), 78);
+    }
+
+    #[test]
+    fn invalid_lists_every_problem() {
+        let problem = Problem::new("words", "Words must be within bounds of 1 and 10, received 0", "Set it to a number from 1 to 10");
+        assert_eq!(RpgenError::Invalid(vec![problem.clone()]).to_string(), format!("{}", problem));
+
+        let problems = RpgenError::Invalid(vec![problem.clone(), problem.clone().within("profiles.wifi")]);
+        assert_eq!(problems.to_string().lines().count(), 3);
+        assert!(problems.to_string().contains("profiles.wifi.words: Words must be"));
+    }
+}
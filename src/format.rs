@@ -0,0 +1,197 @@
+use std::fmt::{self, Write};
+
+use strum_macros::{Display, EnumIter, EnumString, IntoStaticStr};
+use zeroize::Zeroizing;
+
+use crate::password::Password;
+use crate::rules::Rules;
+
+/// How generated passwords are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumIter, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Format {
+    /// One password per line.
+    #[default]
+    Plain,
+    /// An array of objects holding each password, its entropy, its words and the rules.
+    Json,
+    /// A header row followed by one row per password with its entropy and words.
+    Csv,
+    /// Passwords separated by a NUL byte, for use with `xargs -0`.
+    Null,
+}
+
+impl Format {
+    /// Formats the passwords, which were all generated with the rules.
+    ///
+    /// The output is measured before it's written into a buffer of its exact size, which is never reallocated
+    /// and so leaves no copies of the passwords behind, and is zeroed when dropped.
+    pub fn format(&self, passwords: &[Password], rules: &Rules) -> Zeroizing<String> {
+        let mut length = Length(0);
+        self.write(&mut length, passwords, rules).expect("Formatting into memory can't fail");
+        let mut output = Zeroizing::new(String::with_capacity(length.0));
+        self.write(&mut *output, passwords, rules).expect("Formatting into memory can't fail");
+
+        output
+    }
+
+    /// Writes the passwords field by field, so they are never copied into an intermediate value.
+    fn write(&self, out: &mut impl Write, passwords: &[Password], rules: &Rules) -> fmt::Result {
+        match self {
+            Format::Plain | Format::Null => {
+                let terminator = if *self == Format::Plain { '\n' } else { '\0' };
+                for password in passwords {
+                    out.write_str(password.reveal())?;
+                    out.write_char(terminator)?;
+                }
+            }
+            Format::Json => {
+                // Keys are sorted like those of a serde_json object, the rules hold nothing secret.
+                let rules = serde_json::to_value(rules).map_err(|_| fmt::Error)?;
+                out.write_char('[')?;
+                for (i, password) in passwords.iter().enumerate() {
+                    if i > 0 {
+                        out.write_char(',')?;
+                    }
+                    write!(out, "{{\"entropy\":{},\"password\":", serde_json::Value::from(password.entropy().bits()))?;
+                    json_string(out, password.reveal())?;
+                    write!(out, ",\"rules\":{},\"words\":[", rules)?;
+                    for (i, word) in password.reveal_words().iter().enumerate() {
+                        if i > 0 {
+                            out.write_char(',')?;
+                        }
+                        json_string(out, word)?;
+                    }
+                    out.write_str("]}")?;
+                }
+                out.write_str("]\n")?;
+            }
+            Format::Csv => {
+                out.write_str("password,entropy,words\n")?;
+                for password in passwords {
+                    csv_field(out, &[password.reveal()])?;
+                    write!(out, ",{:.2},", password.entropy().bits())?;
+                    csv_field(out, &password.reveal_words().iter().map(String::as_str).collect::<Vec<_>>())?;
+                    out.write_char('\n')?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Counts the bytes written to it, so the output can be given a buffer of its exact size.
+struct Length(usize);
+
+impl Write for Length {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// Writes the string as a JSON string, escaping the same characters as serde_json.
+fn json_string(out: &mut impl Write, string: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in string.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{8}' => out.write_str("\\b")?,
+            '\u{c}' => out.write_str("\\f")?,
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Writes the parts joined by spaces as a single field, quoted if it contains anything which would break the row.
+fn csv_field(out: &mut impl Write, parts: &[&str]) -> fmt::Result {
+    let quoted = parts.iter().any(|part| part.contains([',', '"', '\n', '\r']));
+    if quoted {
+        out.write_char('"')?;
+    }
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            out.write_char(' ')?;
+        }
+        match quoted {
+            true => part.chars().try_for_each(|c| if c == '"' { out.write_str("\"\"") } else { out.write_char(c) })?,
+            false => out.write_str(part)?,
+        }
+    }
+    if quoted {
+        out.write_char('"')?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entropy::Entropy;
+
+    fn passwords() -> Vec<Password> {
+        let entropy = Entropy {
+            words: 30.5,
+            ..Entropy::default()
+        };
+        vec![
+            Password::new("plain-47".to_string(), vec!["plain".to_string()], entropy),
+            Password::new("a,\"b\"ß".to_string(), vec!["a,".to_string(), "\"b\"ß".to_string()], entropy),
+        ]
+    }
+
+    #[test]
+    fn plain_and_null_terminate_every_password() {
+        assert_eq!(*Format::Plain.format(&passwords(), &Rules::default()), "plain-47\na,\"b\"ß\n");
+        assert_eq!(*Format::Null.format(&passwords(), &Rules::default()), "plain-47\0a,\"b\"ß\0");
+    }
+
+    #[test]
+    fn csv_quotes_fields_with_commas_and_quotes() {
+        let csv = Format::Csv.format(&passwords(), &Rules::default());
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines, ["password,entropy,words", "plain-47,30.50,plain", r#""a,""b""ß",30.50,"a, ""b""ß""#]);
+    }
+
+    #[test]
+    fn json_is_an_array_of_password_objects() {
+        let rules = Rules::default();
+        let json = Format::Json.format(&passwords(), &rules);
+        let parsed = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+
+        assert_eq!(
+            parsed,
+            serde_json::json!([
+                { "password": "plain-47", "entropy": 30.5, "words": ["plain"], "rules": rules },
+                { "password": "a,\"b\"ß", "entropy": 30.5, "words": ["a,", "\"b\"ß"], "rules": rules },
+            ])
+        );
+        assert!(json.ends_with("]\n"));
+    }
+
+    #[test]
+    fn json_escapes_like_serde_json() {
+        let mut escaped = String::new();
+        let text = "quote\" slash\\ tab\t nul\0 bell\u{7} ß";
+        json_string(&mut escaped, text).unwrap();
+
+        assert_eq!(escaped, serde_json::to_string(text).unwrap());
+    }
+
+    #[test]
+    fn output_fills_a_buffer_of_its_exact_size() {
+        for format in [Format::Plain, Format::Json, Format::Csv, Format::Null] {
+            let output = format.format(&passwords(), &Rules::default());
+            assert_eq!(output.capacity(), output.len(), "{}", format);
+        }
+    }
+}
@@ -1,212 +1,607 @@
-use rand::rngs::StdRng;
-use rand::{Rng, SeedableRng};
-use rust_embed::EmbeddedFile;
-use serde_json::{Map, Value};
-use simplelog::debug;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
-use crate::asset::Asset;
+use rand::rngs::{OsRng, StdRng};
+use rand::seq::SliceRandom;
+use rand::{CryptoRng, Rng, RngCore, SeedableRng};
+use simplelog::{debug, warn};
+use zeroize::Zeroizing;
+
+use crate::entropy::Entropy;
+use crate::error::RpgenError;
+use crate::mode::Mode;
+use crate::password::Password;
+use crate::pattern::{Pattern, Slot};
+use crate::pronounceable::PronounceableRules;
 use crate::rules::Rules;
+use crate::separator::Separator;
+use crate::wordlist::WordList;
 use crate::Transformation;
 
+/// A cryptographically secure rng which a [`Generator`] can pick with.
+pub trait SecureRng: RngCore + CryptoRng {}
+
+impl<R: RngCore + CryptoRng> SecureRng for R {}
+
+/// How many passwords are generated to estimate the share of them which meet the policy.
+const POLICY_SAMPLES: usize = 1000;
+
+/// Generates passwords according to a set of [`Rules`].
 pub struct Generator {
-    pub rules: Rules,
+    rules: Rules,
     selected_char: Option<char>,
-    seed: StdRng,
-    map: Map<String, Value>,
+    cycle_index: usize,
+    rng: Box<dyn SecureRng>,
+    custom_words: Option<WordList>,
+    words: WordList,
+    pattern: Option<Pattern>,
+    syllables: PronounceableRules,
+    entropy: Entropy,
 }
 
 impl Generator {
-    pub fn new(rules: Rules) -> Generator {
-        debug!("Creating new generator");
+    /// Creates a new generator with an entropy seeded rng,
+    /// using the word list from [`Rules::wordlist`] or the embedded word list for [`Rules::language`] if there is none.
+    pub fn new(rules: Rules) -> Result<Generator, RpgenError> {
+        Generator::with_rng(rules, StdRng::from_rng(OsRng).map_err(RpgenError::Rng)?)
+    }
+
+    /// Creates a new generator which picks with the given rng,
+    /// using the word list from [`Rules::wordlist`] or the embedded word list for [`Rules::language`] if there is none.
+    ///
+    /// Passing a fixed seeded rng makes the generated passwords reproducible, which is only useful for testing.
+    /// Fails with every [problem](Rules::validate) if the rules are invalid.
+    pub fn with_rng<R: SecureRng + 'static>(rules: Rules, rng: R) -> Result<Generator, RpgenError> {
+        Generator::build(rules, None, Box::new(rng))
+    }
 
-        let asset: EmbeddedFile = Asset::get("words.json").unwrap();
-        let str = std::str::from_utf8(asset.data.as_ref()).unwrap();
-        let parsed: Value = serde_json::from_str(&str).unwrap();
-        let map = parsed.as_object().unwrap().clone();
+    /// Creates a new generator with an entropy seeded rng which picks words from the given word list.
+    pub fn with_word_list(rules: Rules, words: WordList) -> Result<Generator, RpgenError> {
+        Generator::build(rules, Some(words), Box::new(StdRng::from_rng(OsRng).map_err(RpgenError::Rng)?))
+    }
 
-        Generator {
+    fn build(rules: Rules, custom_words: Option<WordList>, rng: Box<dyn SecureRng>) -> Result<Generator, RpgenError> {
+        let (words, pattern) = Generator::prepare(&rules, custom_words.as_ref())?;
+
+        let mut generator = Generator {
+            syllables: rules.syllables(),
             rules,
             selected_char: None,
-            seed: StdRng::from_entropy(),
-            map,
+            cycle_index: 0,
+            rng,
+            custom_words,
+            words,
+            pattern,
+            entropy: Entropy::default(),
+        };
+        generator.entropy = generator.estimate_entropy();
+
+        Ok(generator)
+    }
+
+    /// Checks the rules and loads the word list and pattern they use, so nothing is generated from invalid rules.
+    fn prepare(rules: &Rules, custom_words: Option<&WordList>) -> Result<(WordList, Option<Pattern>), RpgenError> {
+        rules.sanity_checks()?;
+        let words = match (&rules.mode, custom_words, &rules.wordlist) {
+            (_, Some(words), _) => words.clone(),
+            (Mode::Words, None, Some(wordlist)) => WordList::load(wordlist)?,
+            (Mode::Words, None, None) => WordList::language(&rules.language)?,
+            _ => WordList::default(),
+        };
+        let words = match rules.ascii_only {
+            true => words.to_ascii(rules.transliterate),
+            false => words,
+        };
+        let words = match rules.exclude_ambiguous {
+            true => words.without_ambiguous(),
+            false => words,
+        };
+        debug!("Creating new generator with {} words", words.len());
+
+        RpgenError::check(rules.validate_words(&words))?;
+
+        let pattern = rules.pattern.as_deref().map(Pattern::from_str).transpose()?;
+        if pattern.iter().flat_map(|p| &p.slots).any(|slot| matches!(slot, Slot::Symbols(_))) && rules.separator_pool().is_empty() {
+            return Err(RpgenError::validation("separator_alphabet", "The pattern has symbols but the separator alphabet is empty"));
+        }
+
+        Ok((words, pattern))
+    }
+
+    /// The rules used for every password this generator creates.
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// Replaces the rules used for every following password, reloading the word list they use.
+    ///
+    /// Fails with every [problem](Rules::validate) and keeps the current rules if the new ones are invalid.
+    pub fn set_rules(&mut self, rules: Rules) -> Result<(), RpgenError> {
+        let (words, pattern) = Generator::prepare(&rules, self.custom_words.as_ref())?;
+        self.syllables = rules.syllables();
+        self.rules = rules;
+        self.words = words;
+        self.pattern = pattern;
+        self.entropy = self.estimate_entropy();
+
+        Ok(())
+    }
+
+    /// The theoretical entropy of a single password generated with the current rules,
+    /// lowered by the share of passwords the [`Policy`](crate::Policy) rejects.
+    pub fn entropy(&self) -> Entropy {
+        self.entropy
+    }
+
+    /// Calculates the entropy of the rules and estimates how much of it the policy takes away,
+    /// since only accepting some passwords makes each of those more likely.
+    fn estimate_entropy(&self) -> Entropy {
+        let accepted = self.policy_acceptance();
+        if accepted < 0.1 {
+            let rejected = (1.0 - accepted) * 100.0;
+            warn!("The policy rejects about {:.0}% of passwords, which lowers their entropy by {:.2} bits", rejected, -accepted.log2());
+        }
+
+        Entropy {
+            policy: accepted.log2(),
+            ..Entropy::calculate(&self.rules, &self.words, self.pattern.as_ref())
+        }
+    }
+
+    /// Estimates the share of passwords which meet the policy from a sample generated with a fixed seed, so it's the same every time.
+    ///
+    /// Weak PINs are left out of the sample, since they are already left out of the entropy of a PIN.
+    fn policy_acceptance(&self) -> f64 {
+        if !self.rules.policy.is_active() {
+            return 1.0;
+        }
+
+        let mut sampler = Generator {
+            rules: self.rules.clone(),
+            selected_char: None,
+            cycle_index: 0,
+            rng: Box::new(StdRng::seed_from_u64(0)),
+            custom_words: None,
+            words: self.words.clone(),
+            pattern: self.pattern.clone(),
+            syllables: self.syllables.clone(),
+            entropy: Entropy::default(),
+        };
+        let (mut sampled, mut accepted) = (0, 0);
+        for _ in 0..POLICY_SAMPLES {
+            let candidate = sampler.candidate();
+            if self.rules.mode == Mode::Pin && !self.rules.pin.weaknesses(candidate.reveal()).is_empty() {
+                continue;
+            }
+            sampled += 1;
+            accepted += self.rules.policy.violations(candidate.reveal()).is_empty() as usize;
         }
+        debug!("The policy accepted {} of {} sampled passwords", accepted, sampled);
+
+        // A policy which rejects the whole sample is still counted as accepting one of them, the generator then fails on its own.
+        accepted.max(1) as f64 / sampled.max(1) as f64
     }
 
-    pub fn generate(&mut self) -> Vec<String> {
+    /// Generates a single password, which is zeroed as soon as it's dropped.
+    fn candidate(&mut self) -> Password {
+        let (password, words) = match (self.rules.mode, self.pattern.clone()) {
+            (Mode::Chars, _) => (self.generate_chars(), Vec::new()),
+            (Mode::Pin, _) => (self.get_digits(self.rules.pin.length), Vec::new()),
+            (_, Some(pattern)) => self.generate_from_pattern(&pattern),
+            (_, None) => self.generate_from_rules(),
+        };
+        // Reset for each password.
+        self.selected_char = None;
+        self.cycle_index = 0;
+
+        Password::new(password, words, self.entropy)
+    }
+
+    /// Generates [`Rules::amount`] passwords, regenerating any which don't meet the [`Policy`](crate::Policy)
+    /// or are weak PINs according to the [`PinRules`](crate::PinRules).
+    ///
+    /// Fails if a password still doesn't meet the policy after [`Policy::max_attempts`](crate::Policy::max_attempts),
+    /// reporting how often each requirement failed.
+    pub fn generate(&mut self) -> Result<Vec<Password>, RpgenError> {
         let mut passwords = Vec::with_capacity(self.rules.amount);
-        // TODO: Holy shit this is ugly and also seems to get the math wrong.
-        let max_length = ((self.rules.max_length * self.rules.amount)
-            + (self.rules.digits_before + self.rules.digits_after)
-            + (match self.rules.separator_char.len() {
-                0 => 0,
-                _ => {
-                    self.rules.words - 1
-                        + match self.rules.digits_before {
-                            0 => 0,
-                            _ => 1,
-                        }
-                }
-            })) as usize;
 
-        debug!("Generating {} passwords with max length {}", self.rules.amount, max_length);
+        debug!("Generating {} passwords", self.rules.amount);
 
         for _ in 0..self.rules.amount {
-            let mut password = String::with_capacity(max_length);
-            let words = self.get_words();
-            let transformed_words = self.transform_words(&words);
-
-            match self.rules.digits_before {
-                0 => debug!("No digits before"),
-                digits => {
-                    debug!("Adding {} digits before", digits);
-                    password.push_str(&*self.get_digits(digits));
-                    self.get_separator().map(|c| password.push(c));
+            let mut failures: BTreeMap<&str, usize> = BTreeMap::new();
+            for attempt in 1.. {
+                // Rejected candidates are zeroed as soon as they're dropped.
+                let candidate = self.candidate();
+                let mut violations = self.rules.policy.violations(candidate.reveal());
+                if self.rules.mode == Mode::Pin {
+                    violations.extend(self.rules.pin.weaknesses(candidate.reveal()));
+                }
+                if violations.is_empty() {
+                    passwords.push(candidate);
+                    break;
+                }
+
+                debug!("Password didn't meet the policy: {:?}", violations);
+                violations.into_iter().for_each(|violation| *failures.entry(violation).or_default() += 1);
+
+                if attempt >= self.rules.policy.max_attempts {
+                    let failures = failures.iter().map(|(rule, count)| format!("{} failed {} times", rule, count)).collect::<Vec<_>>();
+                    return Err(RpgenError::Policy(format!(
+                        "Couldn't generate a password meeting the policy within {} attempts, {}",
+                        self.rules.policy.max_attempts,
+                        failures.join(", ")
+                    )));
                 }
             }
+        }
+
+        Ok(passwords)
+    }
+
+    fn generate_from_rules(&mut self) -> (String, Vec<String>) {
+        let words = Zeroizing::new(self.get_words());
+        let transformation = self.rules.transform;
+        let transformed_words = words.iter().map(|word| self.transform_word(word, transformation)).collect::<Vec<_>>();
+        debug!("Transformed {} words with {:?}", transformed_words.len(), transformation);
+        let mut parts = Zeroizing::new(Vec::with_capacity(transformed_words.len() * 2 + 3));
+
+        match self.rules.digits_before {
+            0 => debug!("No digits before"),
+            digits => {
+                debug!("Adding {} digits before", digits);
+                parts.push(self.get_digits(digits));
+                parts.extend(self.get_separator().map(String::from));
+            }
+        }
+
+        for (i, word) in transformed_words.iter().enumerate() {
+            if i > 0 {
+                parts.extend(self.get_separator().map(String::from));
+            }
+            parts.push(word.clone());
+        }
+
+        match self.rules.digits_after {
+            0 => debug!("No digits after"),
+            digits => {
+                debug!("Adding {} digits after", digits);
+                parts.extend(self.get_separator().map(String::from));
+                parts.push(self.get_digits(digits));
+            }
+        }
+
+        (join(&parts), transformed_words)
+    }
 
-            password.push_str(&*self.add_separators(&transformed_words));
+    fn generate_from_pattern(&mut self, pattern: &Pattern) -> (String, Vec<String>) {
+        let mut parts = Zeroizing::new(Vec::with_capacity(pattern.slots.len()));
+        let mut words = Vec::new();
 
-            match self.rules.digits_after {
-                0 => debug!("No digits after"),
-                digits => {
-                    debug!("Adding {} digits after", digits);
-                    self.get_separator().map(|c| password.push(c));
-                    password.push_str(&*self.get_digits(digits));
+        for slot in &pattern.slots {
+            match slot {
+                Slot::Literal(text) => parts.push(text.clone()),
+                Slot::Word { lengths, transformation } => {
+                    let word = Zeroizing::new(match self.rules.mode {
+                        Mode::Pronounceable => self.get_pronounceable_word(),
+                        _ => self.get_word(*lengths),
+                    });
+                    let word = self.transform_word(&word, *transformation);
+                    parts.push(word.clone());
+                    words.push(word);
+                }
+                Slot::Separator => parts.extend(self.get_separator().map(String::from)),
+                Slot::Digits(count) => parts.push(self.get_digits(*count)),
+                Slot::Symbols(count) => {
+                    let symbols = (0..*count).filter_map(|_| self.get_rand_char()).map(String::from).collect::<Vec<_>>();
+                    parts.push(join(&Zeroizing::new(symbols)));
                 }
             }
+        }
+
+        (join(&parts), words)
+    }
 
-            passwords.push(password);
-            self.selected_char = None; // Reset for each password.
+    /// Picks the minimum of each character class, fills the rest from every class and then shuffles them.
+    fn generate_chars(&mut self) -> String {
+        let mut chars = Zeroizing::new(Vec::with_capacity(self.rules.chars.length));
+        for (_, class, min) in self.rules.chars.classes(self.rules.exclude_ambiguous) {
+            (0..min).for_each(|_| chars.push(class[self.rng.gen_range(0..class.len())]));
         }
 
-        return passwords;
+        let pool = self.rules.chars.pool(self.rules.exclude_ambiguous);
+        while chars.len() < self.rules.chars.length {
+            chars.push(pool[self.rng.gen_range(0..pool.len())]);
+        }
+
+        chars.shuffle(&mut self.rng);
+        debug!("Generated {} random characters", chars.len());
+
+        collect(&chars)
     }
 
     fn get_words(&mut self) -> Vec<String> {
-        let mut words: Vec<String> = Vec::with_capacity(self.rules.amount);
-        for _ in 0..self.rules.words {
-            let length = self.seed.gen_range(self.rules.min_length..self.rules.max_length);
-            let array = self.map.get(length.to_string().as_str()).unwrap();
-            let word = array.get(self.seed.gen_range(0..array.as_array().unwrap().len())).unwrap();
-            words.push(word.as_str().unwrap().to_string());
-        }
-        debug!("Generated words: {:?}", words);
+        let words = match self.rules.mode {
+            Mode::Pronounceable => (0..self.rules.words).map(|_| self.get_pronounceable_word()).collect::<Vec<_>>(),
+            _ => (0..self.rules.words).map(|_| self.get_word(None)).collect::<Vec<_>>(),
+        };
+        debug!("Generated {} words", words.len());
 
         words
     }
 
+    /// Picks a word between the lengths, or following the rules if there are none.
+    fn get_word(&mut self, lengths: Option<(usize, usize)>) -> String {
+        if lengths.is_none() && self.rules.uniform_words {
+            let index = self.rng.gen_range(0..self.words.len());
+            return self.words.get(index).unwrap().to_string();
+        }
+
+        let (min, max) = lengths.unwrap_or((self.rules.min_length, self.rules.max_length));
+        let lengths = self.words.lengths(min, max);
+        let length = lengths[self.rng.gen_range(0..lengths.len())];
+        let bucket = self.words.bucket(length);
+        bucket[self.rng.gen_range(0..bucket.len())].clone()
+    }
+
+    /// Builds a word out of a random number of syllables from the pronounceable tables.
+    fn get_pronounceable_word(&mut self) -> String {
+        let rules = &self.syllables;
+        let mut parts = Zeroizing::new(Vec::new());
+        for _ in 0..self.rng.gen_range(rules.min_syllables..=rules.max_syllables) {
+            parts.push(rules.onsets[self.rng.gen_range(0..rules.onsets.len())].clone());
+            parts.push(rules.vowels[self.rng.gen_range(0..rules.vowels.len())].clone());
+            if self.rng.gen_bool(rules.coda_probability) {
+                parts.push(rules.codas[self.rng.gen_range(0..rules.codas.len())].clone());
+            }
+        }
+
+        join(&parts)
+    }
+
     fn get_digits(&mut self, int: usize) -> String {
-        let mut digits = String::new();
+        let pool = self.rules.digit_pool();
+        let mut digits = String::with_capacity(int * pool.iter().map(|c| c.len_utf8()).max().unwrap_or(1));
         for _ in 0..int {
-            let digit: u32 = self.seed.gen_range(0..9);
-            digits.push(char::from_digit(digit, 10).unwrap());
+            digits.push(pool[self.rng.gen_range(0..pool.len())]);
         }
-        debug!("Generated digits: {}", digits);
+        debug!("Generated {} digits", int);
         digits
     }
 
-    fn transform_words(&mut self, words: &Vec<String>) -> Vec<String> {
-        let mut transformed_words: Vec<String> = Vec::with_capacity(words.len());
-
-        match Transformation::try_from(&*self.rules.transform.to_uppercase()).unwrap() {
-            Transformation::NONE => transformed_words = words.clone(),
-            Transformation::CAPITALISE => words.iter().for_each(|word| {
-                let mut c = word.chars();
-                let str = match c.next() {
-                    None => String::new(),
-                    Some(first) => first.to_uppercase().collect::<String>() + c.as_str(),
-                };
-                transformed_words.push(str);
-            }),
-            Transformation::ALL_EXCEPT_FIRST => words.iter().for_each(|word| {
-                let uppercase = word.to_uppercase();
-                let mut c = uppercase.chars();
-                let str = match c.next() {
-                    None => String::new(),
-                    Some(first) => first.to_lowercase().collect::<String>() + c.as_str(),
-                };
-                transformed_words.push(str);
-            }),
-            Transformation::UPPERCASE => words.iter().for_each(|word| {
-                let uppercase = word.to_uppercase();
-                transformed_words.push(uppercase);
-            }),
-            Transformation::RANDOM => words.iter().for_each(|word| {
-                let mut builder = String::new();
-                for char in word.chars() {
-                    let new = if self.seed.gen::<bool>() {
-                        char.to_uppercase().to_string()
-                    } else {
-                        char.to_lowercase().to_string()
+    /// Transforms the word character by character into a buffer large enough for any character to expand,
+    /// like ß to SS or a long leet substitution, so it's only copied once into a string of the exact size.
+    fn transform_word(&mut self, word: &str, transformation: Transformation) -> String {
+        let widest = self.rules.leet_table.values().map(|leet| leet.chars().count()).fold(3, usize::max);
+        let mut chars = Zeroizing::new(Vec::with_capacity(word.chars().count() * widest));
+        for (i, char) in word.chars().enumerate() {
+            match transformation {
+                Transformation::NONE => chars.push(char),
+                Transformation::CAPITALISE if i == 0 => chars.extend(char.to_uppercase()),
+                Transformation::CAPITALISE => chars.push(char),
+                // Uppercasing can change the number of characters, like ß to SS, so only the rest of the word is uppercased.
+                Transformation::ALL_EXCEPT_FIRST if i == 0 => chars.extend(char.to_lowercase()),
+                Transformation::ALL_EXCEPT_FIRST | Transformation::UPPERCASE => chars.extend(char.to_uppercase()),
+                Transformation::RANDOM if self.rng.gen::<bool>() => chars.extend(char.to_uppercase()),
+                Transformation::RANDOM => chars.extend(char.to_lowercase()),
+                Transformation::ALTERNATING if i % 2 == 0 => chars.extend(char.to_uppercase()),
+                Transformation::ALTERNATING => chars.extend(char.to_lowercase()),
+                Transformation::LEET | Transformation::LEET_RANDOM => {
+                    let substitute = match transformation {
+                        Transformation::LEET => true,
+                        _ => self.rng.gen_bool(self.rules.leet_probability),
                     };
-                    builder.push_str(&*new);
-                }
-                transformed_words.push(builder);
-            }),
-            Transformation::ALTERNATING => words.iter().for_each(|word| {
-                let mut builder = String::new();
-                for (i, char) in word.chars().enumerate() {
-                    let new = if i % 2 == 0 { char.to_uppercase().to_string() } else { char.to_lowercase().to_string() };
-                    builder.push_str(&*new);
+                    match self.rules.leet_substitution(char) {
+                        Some(leet) if substitute => chars.extend(leet.chars()),
+                        _ => chars.push(char),
+                    }
                 }
-                transformed_words.push(builder);
-            }),
+            }
         }
 
-        debug!("Transformed words: {:?}", transformed_words);
-
-        transformed_words
+        collect(&chars)
     }
 
     fn get_rand_char(&mut self) -> Option<char> {
-        let chars = self.rules.separator_alphabet.chars().collect::<Vec<char>>();
-        if chars.len() <= 0 {
+        let chars = self.rules.separator_pool().chars().collect::<Vec<char>>();
+        if chars.is_empty() {
             return None;
         }
-        Some(chars[self.seed.gen::<usize>() % chars.len()].clone())
+        Some(chars[self.rng.gen::<usize>() % chars.len()])
     }
 
     fn get_separator(&mut self) -> Option<char> {
-        match &*self.rules.separator_char {
-            "NONE" => {
+        match &self.rules.separator_char {
+            Separator::None => {
                 debug!("No separator char");
                 None
             }
-            "RANDOM" => {
+            Separator::Random => {
                 debug!("Random separator char");
                 if self.rules.match_random_char {
                     debug!("Using the same random char for all separators");
                     if self.selected_char.is_none() {
                         self.selected_char = self.get_rand_char();
-                        debug!("No random char selected, generating one: {}", self.selected_char.unwrap());
+                        debug!("No random char selected, generating one");
                     }
                     self.selected_char
                 } else {
-                    let char = self.get_rand_char();
-                    debug!("Random char selected: {:?}", char);
-                    char
+                    self.get_rand_char()
                 }
             }
-            _ => {
-                debug!("Separator char: {}", &*self.rules.separator_char);
-                self.rules.separator_char.chars().min()
+            Separator::Fixed(char) => {
+                debug!("Separator char: {}", char);
+                Some(*char)
+            }
+            Separator::Cycle(chars) => {
+                let char = chars[self.cycle_index % chars.len()];
+                debug!("Cycling separator char: {}", char);
+                self.cycle_index += 1;
+                Some(char)
             }
         }
     }
+}
 
-    fn add_separators(&mut self, words: &Vec<String>) -> String {
-        let mut builder = String::new();
-        let mut itr = words.iter();
+/// Joins the parts into a string of the exact size, so it's never reallocated which would leave copies of the password behind.
+fn join(parts: &[String]) -> String {
+    let mut joined = String::with_capacity(parts.iter().map(String::len).sum());
+    parts.iter().for_each(|part| joined.push_str(part));
+    joined
+}
 
-        while itr.len() >= 1 {
-            let word = itr.next().unwrap();
-            builder.push_str(word);
-            if itr.len() > 0 {
-                builder.push(self.get_separator().unwrap());
-            }
+/// Collects the characters into a string of the exact size, for the same reason as [`join`].
+fn collect(chars: &[char]) -> String {
+    let mut collected = String::with_capacity(chars.iter().map(|c| c.len_utf8()).sum());
+    chars.iter().for_each(|&c| collected.push(c));
+    collected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PinRules;
+    use crate::Policy;
+
+    fn generator(rules: Rules, seed: u64) -> Generator {
+        let words = WordList::from_words(["cat", "dog", "owl", "straße"]);
+        let rules = Rules {
+            min_length: 3,
+            max_length: 3,
+            ..rules
+        };
+
+        Generator::build(rules, Some(words), Box::new(StdRng::seed_from_u64(seed))).unwrap()
+    }
+
+    fn reveal(passwords: &[Password]) -> Vec<&str> {
+        passwords.iter().map(Password::reveal).collect()
+    }
+
+    #[test]
+    fn the_same_seed_generates_the_same_passwords() {
+        let first = generator(Rules::default(), 7).generate().unwrap();
+        let second = generator(Rules::default(), 7).generate().unwrap();
+
+        assert_eq!(first.len(), Rules::default().amount);
+        assert_eq!(reveal(&first), reveal(&second));
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected_and_the_current_ones_kept() {
+        let mut generator = generator(Rules::default(), 0);
+
+        let rules = Rules { min_length: 20, ..generator.rules().clone() };
+        assert!(matches!(generator.set_rules(rules), Err(RpgenError::Invalid(_))));
+        let rules = Rules {
+            mode: Mode::Pin,
+            pin: PinRules { length: 30, ..PinRules::default() },
+            ..generator.rules().clone()
+        };
+        assert!(matches!(generator.set_rules(rules), Err(RpgenError::Invalid(_))));
+        assert_eq!(generator.rules().min_length, 3);
+        assert!(generator.generate().is_ok());
+
+        generator.set_rules(Rules { words: 2, ..generator.rules().clone() }).unwrap();
+        assert!(generator.generate().unwrap().iter().all(|password| password.reveal_words().len() == 2));
+    }
+
+    #[test]
+    fn rules_put_separators_between_the_digits_and_words() {
+        let rules = Rules {
+            words: 3,
+            digits_before: 2,
+            digits_after: 3,
+            separator_char: Separator::Fixed('-'),
+            transform: Transformation::NONE,
+            ..Rules::default()
+        };
+
+        for password in generator(rules, 1).generate().unwrap() {
+            let parts = password.reveal().split('-').collect::<Vec<_>>();
+            assert_eq!(parts.len(), 5, "{}", password.reveal());
+            assert!(parts[0].len() == 2 && parts[0].chars().all(|c| c.is_ascii_digit()));
+            assert!(parts[1..4].iter().all(|word| ["cat", "dog", "owl"].contains(word)));
+            assert_eq!(parts[1..4], password.reveal_words()[..]);
+            assert!(parts[4].len() == 3 && parts[4].chars().all(|c| c.is_ascii_digit()));
         }
+    }
+
+    #[test]
+    fn cycles_restart_for_every_password() {
+        let rules = Rules {
+            words: 3,
+            digits_after: 1,
+            separator_char: Separator::Cycle(vec!['-', '+']),
+            ..Rules::default()
+        };
+
+        for password in generator(rules, 2).generate().unwrap() {
+            let separators = password.reveal().chars().filter(|c| !c.is_alphanumeric()).collect::<String>();
+            assert_eq!(separators, "-+-");
+        }
+    }
+
+    #[test]
+    fn transformations_handle_characters_which_change_length() {
+        let mut generator = generator(Rules::default(), 3);
+        let words = ["straße", "owl"];
+        let mut transform = |transformation| words.iter().map(|word| generator.transform_word(word, transformation)).collect::<Vec<_>>();
+
+        assert_eq!(transform(Transformation::NONE), ["straße", "owl"]);
+        assert_eq!(transform(Transformation::CAPITALISE), ["Straße", "Owl"]);
+        assert_eq!(transform(Transformation::ALL_EXCEPT_FIRST), ["sTRASSE", "oWL"]);
+        assert_eq!(transform(Transformation::UPPERCASE), ["STRASSE", "OWL"]);
+        assert_eq!(transform(Transformation::ALTERNATING), ["StRaSSe", "OwL"]);
+        assert_eq!(transform(Transformation::LEET), ["$7r4ß3", "0wl"]);
+    }
+
+    #[test]
+    fn patterns_fill_every_slot_in_order() {
+        let rules = Rules {
+            pattern: Some(Box::from("{WORD}{sep}{d:2}!{sym:2}")),
+            separator_char: Separator::Fixed('_'),
+            separator_alphabet: Box::from("#"),
+            ..Rules::default()
+        };
+
+        for password in generator(rules, 4).generate().unwrap() {
+            let (word, rest) = password.reveal().split_once('_').unwrap();
+            assert!(["CAT", "DOG", "OWL"].contains(&word));
+            assert_eq!(password.reveal_words(), [word]);
+            assert!(rest[..2].chars().all(|c| c.is_ascii_digit()));
+            assert_eq!(&rest[2..], "!##");
+        }
+    }
+
+    #[test]
+    fn policy_rejections_lower_the_entropy() {
+        let unconstrained = Generator::with_rng(Rules::default(), StdRng::seed_from_u64(0)).unwrap().entropy();
+        assert_eq!(unconstrained.policy, 0.0);
+
+        let mut rules = Rules::default();
+        rules.policy.max_length = Some(15);
+        let mut generator = Generator::with_rng(rules, StdRng::seed_from_u64(0)).unwrap();
+        let entropy = generator.entropy();
+
+        // Only words adding up to at most 9 characters fit, which most pairs of words don't.
+        assert!(entropy.policy < -1.0, "{}", entropy.policy);
+        assert_eq!(entropy.bits(), unconstrained.bits() + entropy.policy);
+        assert!(generator.generate().unwrap().iter().all(|password| password.entropy() == entropy));
+    }
+
+    #[test]
+    fn policy_failures_are_counted_once_attempts_run_out() {
+        let rules = Rules {
+            policy: Policy {
+                min_length: Some(100),
+                max_attempts: 5,
+                ..Policy::default()
+            },
+            ..Rules::default()
+        };
 
-        debug!("Final string: {}", builder);
-        builder
+        let error = generator(rules, 5).generate().unwrap_err();
+        assert!(matches!(error, RpgenError::Policy(_)));
+        assert!(error.to_string().contains("within 5 attempts, min_length failed 5 times"), "{}", error);
     }
 }
@@ -0,0 +1,51 @@
+//! Memorable password generation.
+//!
+//! The [`Generator`] takes a set of [`Rules`] and produces passwords built from words picked out of
+//! the embedded or a custom [`WordList`], joined by separator characters and padded with digits.
+//!
+//! ```
+//! use rpgen::{Generator, Rules};
+//!
+//! let mut generator = Generator::new(Rules::default()).unwrap();
+//! let passwords = generator.generate().unwrap();
+//! assert_eq!(passwords.len(), Rules::default().amount);
+//! assert!(passwords[0].entropy().bits() > 0.0);
+//! ```
+
+pub mod ambiguous;
+pub mod ascii;
+mod asset;
+pub mod chars;
+pub mod config;
+pub mod entropy;
+pub mod error;
+pub mod format;
+pub mod generator;
+pub mod mode;
+pub mod password;
+pub mod pattern;
+pub mod pin;
+pub mod policy;
+pub mod pronounceable;
+pub mod rules;
+pub mod schema;
+pub mod separator;
+pub mod transformation;
+pub mod wordlist;
+
+pub use crate::chars::CharRules;
+pub use crate::config::{Config, ConfigFormat, Source};
+pub use crate::entropy::Entropy;
+pub use crate::error::{Problem, RpgenError};
+pub use crate::format::Format;
+pub use crate::generator::Generator;
+pub use crate::mode::Mode;
+pub use crate::password::Password;
+pub use crate::pattern::Pattern;
+pub use crate::pin::PinRules;
+pub use crate::policy::Policy;
+pub use crate::pronounceable::PronounceableRules;
+pub use crate::rules::Rules;
+pub use crate::separator::Separator;
+pub use crate::transformation::Transformation;
+pub use crate::wordlist::WordList;
@@ -1,80 +1,122 @@
-mod asset;
-mod generator;
-mod rules;
-mod transformation;
-
-use crate::generator::Generator;
-use crate::rules::Rules;
-use crate::transformation::Transformation;
-use clap::{arg, command, Arg, ArgMatches, Command};
-use simplelog::{debug, error, info, ColorChoice, CombinedLogger, ConfigBuilder, LevelFilter, SharedLogger, TermLogger, TerminalMode, WriteLogger};
+use clap::{arg, command, value_parser, Arg, ArgMatches, Command, PossibleValue};
+use clap_complete::{generate, Shell};
+use clap_mangen::Man;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use simplelog::{debug, error, info, warn, ColorChoice, CombinedLogger, ConfigBuilder, LevelFilter, SharedLogger, TermLogger, TerminalMode, WriteLogger};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs::{create_dir, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::{env, fs, process};
+use std::{env, io, process};
 use strum::IntoEnumIterator;
 
+use rpgen::schema;
+use rpgen::wordlist::{BUILTIN, LANGUAGES};
+use rpgen::{Config, Format, Generator, Mode, RpgenError, Rules, Source, Transformation};
+
 fn main() {
     let matches = get_cli();
-    let mut rules = init(&matches).map_err(|e| handle_error(e.0, e.1)).unwrap();
-    if let Some(supplied_rules) = pass_supplied(&matches).map_err(|(s, e)| handle_error(s, e)).unwrap() {
-        rules = supplied_rules;
+    match matches.subcommand() {
+        Some(("completions", completions)) => {
+            let shell = *completions.get_one::<Shell>("SHELL").unwrap();
+            return with_cli(|mut cli| generate(shell, &mut cli, env!("CARGO_BIN_NAME"), &mut io::stdout()));
+        }
+        Some(("man", _)) => {
+            return with_cli(|cli| Man::new(cli).render(&mut io::stdout())).unwrap_or_else(|err| {
+                handle_error(RpgenError::Io {
+                    path: PathBuf::from("stdout"),
+                    source: err,
+                })
+            });
+        }
+        _ => {}
+    }
+    init(&matches).unwrap_or_else(handle_error);
+    match matches.subcommand() {
+        Some(("config", config)) => return run_config(&matches, config).unwrap_or_else(handle_error),
+        Some(("profiles", profiles)) => return list_profiles(profiles).unwrap_or_else(handle_error),
+        _ => {}
     }
-    pass_args(&mut rules, &matches);
-    rules.sanity_checks().map_err(|e| handle_error(e, None)).unwrap();
+
+    let subcommand = matches.subcommand().unwrap().1; // It should be safe i think
+    let config = load_config(&matches, subcommand).unwrap_or_else(handle_error);
+    // The generator checks the rules again, but only the config knows about the keys its layers skipped.
+    config.sanity_checks().unwrap_or_else(handle_error);
+    let rules = config.rules;
 
     debug!("Final rule set: {:?}", rules);
 
-    let mut generator = Generator::new(rules);
-    let passwords = generator.generate();
+    let generator = match matches.value_of("SEED") {
+        Some(seed) => {
+            let seed = parse_seed(seed).unwrap_or_else(handle_error);
+            warn!("Using the fixed seed {}, these passwords are reproducible and must not be used for anything real!", seed);
+            Generator::with_rng(rules, StdRng::seed_from_u64(seed))
+        }
+        None => Generator::new(rules),
+    };
+    let mut generator = generator.unwrap_or_else(handle_error);
+    match matches.subcommand_name() {
+        Some("entropy") => println!("{}", generator.entropy()),
+        _ => {
+            let format = match matches.value_of("FORMAT") {
+                Some(format) => Format::from_str(format).map_err(|_| {
+                    let formats = Format::iter().map(|f| f.to_string()).collect::<Vec<_>>();
+                    RpgenError::Validation {
+                        field: "format".to_string(),
+                        reason: format!("Unknown format {}, expected one of {:?}", format, formats),
+                    }
+                }),
+                None => Ok(Format::default()),
+            };
+            let format = format.unwrap_or_else(handle_error);
+            let passwords = generator.generate().unwrap_or_else(handle_error);
 
-    info!("Generated passwords:\n\n{}\n", passwords.join("\n"));
+            info!("Generated {} passwords with {:.2} bits of entropy each", passwords.len(), generator.entropy().bits());
+            print!("{}", *format.format(&passwords, generator.rules()));
+        }
+    }
 }
 
-pub fn handle_error(reason: String, err: Option<Box<dyn Error>>) {
-    error!("{}", reason);
-    err.map(|e| debug!("{:?}", e));
-    process::exit(1);
+pub fn handle_error<T>(err: RpgenError) -> T {
+    error!("{}", err);
+    if let Some(source) = err.source() {
+        debug!("{:?}", source);
+    }
+    process::exit(err.exit_code());
 }
 
-fn pass_supplied(matches: &ArgMatches) -> Result<Option<Rules>, (String, Option<Box<dyn Error>>)> {
-    let subcommand = matches.subcommand().unwrap().1; // It should be safe i think
+/// The path of the config file supplied to the subcommand, if there is one.
+fn pass_supplied(subcommand: &ArgMatches) -> Result<Option<PathBuf>, RpgenError> {
     let path = match subcommand.value_of("CONFIG").map(|p| {
         let mut temp_path = PathBuf::from(p);
         if !temp_path.exists() || {
             (temp_path = Path::new(env::current_dir().unwrap().to_str().unwrap()).join(temp_path));
             !temp_path.exists()
         } {
-            Err(format!("File {} does not exist", temp_path.display()))
+            Err(RpgenError::Io {
+                source: io::Error::new(io::ErrorKind::NotFound, "File does not exist"),
+                path: temp_path,
+            })
         } else {
             Ok(temp_path)
         }
     }) {
-        Some(Ok(path)) => path,
-        Some(Err(reason)) => Err((reason, None))?,
+        Some(path) => path?,
         _ => return Ok(None),
     };
 
     debug!("Trying to read file: {}", path.display());
 
-    if !path.exists() || !path.is_file() {
-        return Err((format!("File {} does not exist or isn't a file.", path.display()), None));
-    }
-
-    return match fs::read_to_string(&path) {
-        Ok(string) => match toml::from_str::<Rules>(&string) {
-            Ok(rules) => rules.sanity_checks().map_err(|e| (e, None)).map(|_| Some(rules)),
-            Err(err) => Err((format!("Couldn't parse file {}", path.display()), Some(Box::new(err)))),
-        },
-        Err(err) => Err((format!("Couldn't read {}", path.display()), Some(Box::new(err)))),
-    };
+    Ok(Some(path))
 }
 
-fn pass_args(rules: &mut Rules, matches: &ArgMatches) {
+/// Applies the arguments to the rules, returning the dotted path of every rule which was set.
+fn pass_args(rules: &mut Rules, matches: &ArgMatches) -> Result<Vec<&'static str>, RpgenError> {
     let mut args = HashMap::new();
+    let mut set = Vec::new();
     matches.value_of("WORDS").map(|words| args.insert("words", words));
     matches.value_of("MIN_LENGTH").map(|min_length| args.insert("min_length", min_length));
     matches.value_of("MAX_LENGTH").map(|max_length| args.insert("max_length", max_length));
@@ -84,44 +126,127 @@ fn pass_args(rules: &mut Rules, matches: &ArgMatches) {
     matches.value_of("SEPARATOR_CHAR").map(|separator_char| args.insert("separator_char", separator_char));
     matches.value_of("SEPARATOR_ALPHABET").map(|separator_alphabet| args.insert("separator_alphabet", separator_alphabet));
     matches.value_of("TRANSFORM").map(|transform| args.insert("transform", transform));
+    matches.value_of("WORDLIST").map(|wordlist| args.insert("wordlist", wordlist));
+    matches.value_of("LANGUAGE").map(|language| args.insert("language", language));
+    matches.value_of("PATTERN").map(|pattern| args.insert("pattern", pattern));
     if matches.is_present("MATCH_RANDOM_CHAR") {
-        rules.match_random_char = false
+        rules.match_random_char = false;
+        set.push("match_random_char");
+    }
+    if matches.is_present("UNIFORM_WORDS") {
+        rules.uniform_words = true;
+        set.push("uniform_words");
+    }
+    if matches.is_present("ASCII_ONLY") {
+        rules.ascii_only = true;
+        set.push("ascii_only");
+    }
+    if matches.is_present("EXCLUDE_AMBIGUOUS") {
+        rules.exclude_ambiguous = true;
+        set.push("exclude_ambiguous");
+    }
+    if matches.is_present("NO_TRANSLITERATE") {
+        rules.transliterate = false;
+        set.push("transliterate");
+    }
+    match matches.subcommand() {
+        Some(("chars", chars)) => {
+            rules.mode = Mode::Chars;
+            set.push("mode");
+            if let Some(length) = chars.value_of("LENGTH") {
+                rules.chars.length = parse_arg("chars.length", length)?;
+                set.push("chars.length");
+            }
+        }
+        Some(("pronounceable", pronounceable)) => {
+            rules.mode = Mode::Pronounceable;
+            set.push("mode");
+            if let Some(min_syllables) = pronounceable.value_of("MIN_SYLLABLES") {
+                rules.pronounceable.min_syllables = parse_arg("pronounceable.min_syllables", min_syllables)?;
+                set.push("pronounceable.min_syllables");
+            }
+            if let Some(max_syllables) = pronounceable.value_of("MAX_SYLLABLES") {
+                rules.pronounceable.max_syllables = parse_arg("pronounceable.max_syllables", max_syllables)?;
+                set.push("pronounceable.max_syllables");
+            }
+        }
+        Some(("pin", pin)) => {
+            rules.mode = Mode::Pin;
+            set.push("mode");
+            if let Some(length) = pin.value_of("LENGTH") {
+                rules.pin.length = parse_arg("pin.length", length)?;
+                set.push("pin.length");
+            }
+        }
+        _ => {}
     }
 
     debug!("Supplied arguments {:?}", args);
 
     for (arg, value) in args {
         match arg {
-            "words" => rules.words = unwrap_or_exit(&value),
-            "min_length" => rules.min_length = unwrap_or_exit(&value),
-            "max_length" => rules.max_length = unwrap_or_exit(&value),
-            "digits_before" => rules.digits_before = unwrap_or_exit(&value),
-            "digits_after" => rules.digits_after = unwrap_or_exit(&value),
-            "amount" => rules.amount = unwrap_or_exit(&value),
-            "separator_char" => rules.separator_char = Box::from(value),
+            "words" => rules.words = parse_arg(arg, value)?,
+            "min_length" => rules.min_length = parse_arg(arg, value)?,
+            "max_length" => rules.max_length = parse_arg(arg, value)?,
+            "digits_before" => rules.digits_before = parse_arg(arg, value)?,
+            "digits_after" => rules.digits_after = parse_arg(arg, value)?,
+            "amount" => rules.amount = parse_arg(arg, value)?,
+            "separator_char" => rules.separator_char = parse_arg(arg, value)?,
             "separator_alphabet" => rules.separator_alphabet = Box::from(value),
-            "transform" => rules.transform = Box::from(value),
-            "match_random_char" => rules.match_random_char = unwrap_or_exit(&value),
+            "transform" => rules.transform = parse_arg(arg, value)?,
+            "wordlist" => rules.wordlist = Some(Box::from(value)),
+            "language" => rules.language = Box::from(value),
+            "pattern" => rules.pattern = Some(Box::from(value)),
+            "match_random_char" => rules.match_random_char = parse_arg(arg, value)?,
             _ => {}
         }
+        set.push(arg);
+    }
+
+    Ok(set)
+}
+
+fn parse_seed(seed: &str) -> Result<u64, RpgenError> {
+    if let Ok(seed) = seed.parse::<u64>() {
+        return Ok(seed);
     }
+
+    let hex = seed.strip_prefix("0x").or_else(|| seed.strip_prefix("0X")).unwrap_or(seed);
+    u64::from_str_radix(hex, 16).map_err(|_| RpgenError::Validation {
+        field: "seed".to_string(),
+        reason: format!("Couldn't parse seed {} as a u64 or hex", seed),
+    })
 }
 
-fn unwrap_or_exit<T>(str: &str) -> T
+fn parse_arg<T>(field: &str, str: &str) -> Result<T, RpgenError>
 where
     T: FromStr,
 {
-    match str.parse::<T>() {
-        Ok(value) => value,
-        Err(_) => {
-            error!("Couldn't parse {} as {}", str, stringify!(T));
-            process::exit(1);
-        }
+    str.parse::<T>().map_err(|_| RpgenError::Validation {
+        field: field.to_string(),
+        reason: format!("Couldn't parse {} as {}", str, kind::<T>()),
+    })
+}
+
+/// A readable name for the type an argument is parsed as.
+fn kind<T>() -> String {
+    match std::any::type_name::<T>().rsplit("::").next().unwrap_or_default() {
+        "usize" | "u64" | "u32" => "a whole number".to_string(),
+        "f64" => "a number".to_string(),
+        "bool" => "true or false".to_string(),
+        "Transformation" => format!("one of {:?}", Transformation::iter().map(<&str>::from).collect::<Vec<_>>()),
+        _ => "a valid value".to_string(),
     }
 }
 
 fn get_cli() -> ArgMatches {
-    return command!()
+    with_cli(|cli| cli.get_matches())
+}
+
+/// Builds the command line interface and hands it to the closure,
+/// which is needed since the formatted help messages only live until the end of this call.
+fn with_cli<T>(f: impl FnOnce(Command) -> T) -> T {
+    f(command!()
         .propagate_version(true)
         .subcommand_required(true)
         .arg_required_else_help(true)
@@ -132,12 +257,12 @@ fn get_cli() -> ArgMatches {
                 .short('w')
                 .long("words"),
             Arg::new("MIN_LENGTH")
-                .help(format!("The minimum length of each word (default: {}, min: 3)", Rules::default().min_length).as_str())
+                .help(format!("The minimum length of each word (default: {}, min: 1)", Rules::default().min_length).as_str())
                 .takes_value(true)
                 .short('m')
                 .long("min-length"),
             Arg::new("MAX_LENGTH")
-                .help(format!("The maximum length of each word (default: {}, max: 9)", Rules::default().max_length).as_str())
+                .help(format!("The maximum length of each word (default: {}, up to the longest word in the list)", Rules::default().max_length).as_str())
                 .takes_value(true)
                 .short('M')
                 .long("max-length"),
@@ -153,18 +278,21 @@ fn get_cli() -> ArgMatches {
                 .long("digits-after"),
             Arg::new("TRANSFORM")
                 .help(
-                    format!(
-                        "What transformation mode to use, Options are {:?} (default: {})",
-                        Transformation::iter().collect::<Vec<_>>(),
-                        Rules::default().transform
-                    )
-                    .as_str(),
+                    format!("What transformation mode to use (default: {})", Rules::default().transform).as_str(),
                 )
                 .takes_value(true)
+                .possible_values(Transformation::iter().map(|t| PossibleValue::new(t.into())))
+                .ignore_case(true)
                 .short('t')
                 .long("transform"),
             Arg::new("SEPARATOR_CHAR")
-                .help(format!("The character to use to separate the words (default: \"{}\")", Rules::default().separator_char).as_str())
+                .help(
+                    format!(
+                        "The character to use to separate the words, RANDOM, NONE or several characters to cycle through (default: \"{}\")",
+                        Rules::default().separator_char
+                    )
+                    .as_str(),
+                )
                 .takes_value(true)
                 .short('s')
                 .long("separator-char"),
@@ -188,14 +316,144 @@ fn get_cli() -> ArgMatches {
                 .takes_value(true)
                 .short('a')
                 .long("amount"),
+            Arg::new("WORDLIST")
+                .help(
+                    format!(
+                        "A built-in word list, Options are {:?}, or the path to a newline delimited or JSON word list (default: the language's word list)",
+                        BUILTIN.iter().map(|(name, _)| *name).collect::<Vec<_>>()
+                    )
+                    .as_str(),
+                )
+                .takes_value(true)
+                .long("wordlist"),
+            Arg::new("LANGUAGE")
+                .help(
+                    format!("The language of the embedded word list used when there is no word list (default: {})", Rules::default().language).as_str(),
+                )
+                .takes_value(true)
+                .possible_values(LANGUAGES.iter().map(|(code, _)| PossibleValue::new(code)))
+                .ignore_case(true)
+                .long("language"),
+            Arg::new("UNIFORM_WORDS")
+                .help(
+                    format!(
+                        "Pick words uniformly from the whole word list ignoring the min and max length (default: {})",
+                        Rules::default().uniform_words
+                    )
+                    .as_str(),
+                )
+                .short('u')
+                .long("uniform-words"),
+            Arg::new("ASCII_ONLY")
+                .help(
+                    format!(
+                        "Only use characters which can be typed on any keyboard, transliterating accented words (default: {})",
+                        Rules::default().ascii_only
+                    )
+                    .as_str(),
+                )
+                .long("ascii-only"),
+            Arg::new("EXCLUDE_AMBIGUOUS")
+                .help(
+                    format!(
+                        "Leave out characters which are easily mistaken for one another like l, 1 and I (default: {})",
+                        Rules::default().exclude_ambiguous
+                    )
+                    .as_str(),
+                )
+                .short('x')
+                .long("exclude-ambiguous"),
+            Arg::new("NO_TRANSLITERATE")
+                .help("Drop accented words instead of transliterating them when only using ASCII")
+                .long("no-transliterate"),
+            Arg::new("PATTERN")
+                .help("A pattern like \"{Word}{sep}{word:4-6}{sep}{d:3}{sym}\" describing each password, replacing the word, transform and digit options")
+                .takes_value(true)
+                .short('p')
+                .long("pattern"),
+            Arg::new("SEED")
+                .help("Seed the generator with a u64 or hex value for reproducible output, never use these passwords")
+                .takes_value(true)
+                .long("seed"),
+            Arg::new("FORMAT")
+                .help(format!("How the passwords are written to stdout (default: {})", Format::default()).as_str())
+                .takes_value(true)
+                .possible_values(Format::iter().map(|f| PossibleValue::new(f.into())))
+                .ignore_case(true)
+                .short('f')
+                .long("format"),
+            Arg::new("PROFILE")
+                .help("Use a [profiles.<name>] section of the config file on top of its defaults")
+                .takes_value(true)
+                .long("profile")
+                .env("RPGEN_PROFILE"),
             Arg::new("DEBUG").help("Enable debug logging").long("debug"),
             Arg::new("LOG").help("Enable saving output to a log file.").long("log").takes_value(true),
         ])
-        .subcommand(Command::new("generate").about("Generate some new passwords.").arg(arg!([CONFIG] "The config file to use.")))
-        .get_matches();
+        .subcommand(Command::new("generate").about("Generate some new passwords.").arg(config_arg()))
+        .subcommand(
+            Command::new("entropy")
+                .about("Calculate the entropy of the rules without generating any passwords.")
+                .arg(config_arg()),
+        )
+        .subcommand(
+            Command::new("chars")
+                .about("Generate some new passwords of random characters instead of words.")
+                .arg(config_arg())
+                .arg(Arg::new("LENGTH").help("The number of characters in each password").takes_value(true).short('l').long("length")),
+        )
+        .subcommand(
+            Command::new("pin")
+                .about("Generate some new numeric PINs, rejecting sequences, repeats, palindromes, dates and common PINs.")
+                .arg(config_arg())
+                .arg(Arg::new("LENGTH").help("The number of digits in each PIN").takes_value(true).short('l').long("length")),
+        )
+        .subcommand(
+            Command::new("pronounceable")
+                .about("Generate some new passwords from pronounceable made up words instead of dictionary words.")
+                .arg(config_arg())
+                .args(&[
+                    Arg::new("MIN_SYLLABLES").help("The minimum number of syllables in each word").takes_value(true).long("min-syllables"),
+                    Arg::new("MAX_SYLLABLES").help("The maximum number of syllables in each word").takes_value(true).long("max-syllables"),
+                ]),
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Print the completion script for a shell.")
+                .arg(Arg::new("SHELL").help("The shell to complete for").required(true).value_parser(value_parser!(Shell))),
+        )
+        .subcommand(Command::new("man").about("Print the man page."))
+        .subcommand(
+            Command::new("profiles")
+                .about("List the profiles in the config file.")
+                .arg(config_arg()),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Manage the config file.")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("init")
+                        .about("Write the default config file.")
+                        .arg(Arg::new("FORCE").help("Overwrite the config file if it already exists").short('f').long("force")),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Show the rules after applying the config files and arguments, with where each value came from.")
+                        .arg(config_arg()),
+                )
+                .subcommand(Command::new("path").about("Print the path of the default config file."))
+                .subcommand(Command::new("schema").about("Print a JSON Schema of the config file for editors to validate and autocomplete with."))
+                .subcommand(Command::new("validate").about("Check a config file for mistakes.").arg(arg!(<CONFIG> "The config file to check."))),
+        ))
+}
+
+/// The optional config file argument shared by the subcommands.
+fn config_arg<'help>() -> Arg<'help> {
+    arg!([CONFIG] "The config file to use.").env("RPGEN_CONFIG")
 }
 
-fn init(matches: &ArgMatches) -> Result<Rules, (String, Option<Box<dyn Error>>)> {
+fn init(matches: &ArgMatches) -> Result<(), RpgenError> {
     let level = match matches.is_present("DEBUG") {
         true => LevelFilter::Debug,
         false => LevelFilter::Info,
@@ -203,7 +461,7 @@ fn init(matches: &ArgMatches) -> Result<Rules, (String, Option<Box<dyn Error>>)>
     let mut vec: Vec<Box<dyn SharedLogger>> = Vec::new();
     let term_config = ConfigBuilder::new().set_time_level(LevelFilter::Off).build();
 
-    vec.push(TermLogger::new(level, term_config.clone(), TerminalMode::Mixed, ColorChoice::Auto));
+    vec.push(TermLogger::new(level, term_config.clone(), TerminalMode::Stderr, ColorChoice::Auto));
     if matches.is_present("LOG") {
         let log_file_str = matches.value_of("LOG").unwrap_or("pgen.log");
         let log_file = if let Ok(log_file) = File::open(log_file_str) {
@@ -211,7 +469,12 @@ fn init(matches: &ArgMatches) -> Result<Rules, (String, Option<Box<dyn Error>>)>
         } else {
             match File::create(log_file_str) {
                 Ok(log_file) => log_file,
-                Err(err) => return Err((format!("Could not create log file: {}", err), Some(Box::new(err)))),
+                Err(err) => {
+                    return Err(RpgenError::Io {
+                        path: PathBuf::from(log_file_str),
+                        source: err,
+                    })
+                }
             }
         };
         vec.push(WriteLogger::new(level, term_config, log_file));
@@ -219,55 +482,152 @@ fn init(matches: &ArgMatches) -> Result<Rules, (String, Option<Box<dyn Error>>)>
 
     CombinedLogger::init(vec).unwrap();
 
-    return match env::consts::OS {
-        "windows" | "linux" | "macos" => {
-            let target_dir = dirs::config_dir().unwrap().join("PGen");
+    Ok(())
+}
 
-            match target_dir.exists() || create_dir(&target_dir).is_ok() {
-                true => get_config(&target_dir),
-                false => Err((format!("Couldn't create directory {}", target_dir.display()), None)),
-            }
-        }
-        _ => Err(("Unsupported OS".to_string(), None)),
+/// The path of the default config file, which may not exist yet.
+fn config_path() -> Result<PathBuf, RpgenError> {
+    match env::consts::OS {
+        "windows" | "linux" | "macos" => Ok(dirs::config_dir().unwrap().join("PGen").join("PGen.conf")),
+        _ => Err(RpgenError::Io {
+            path: PathBuf::new(),
+            source: io::Error::new(io::ErrorKind::Unsupported, "Unsupported OS"),
+        }),
+    }
+}
+
+/// Writes the default rules to the config file, creating its directory if needed.
+fn write_default_config(config_file: &Path) -> Result<(), RpgenError> {
+    let io_error = |err| RpgenError::Io {
+        path: config_file.to_path_buf(),
+        source: err,
     };
+    if let Some(target_dir) = config_file.parent().filter(|dir| !dir.exists()) {
+        create_dir(target_dir).map_err(io_error)?;
+    }
+
+    let string = toml::ser::to_string_pretty(&Rules::default()).unwrap();
+    let mut file = File::create(config_file).map_err(io_error)?;
+    file.write_all(string.as_bytes()).map_err(io_error)
 }
 
-fn get_config(target_dir: &Path) -> Result<Rules, (String, Option<Box<dyn Error>>)> {
-    let config_file = target_dir.join("PGen.conf");
+/// The default config file, which is created on the first run, followed by the config file supplied to the subcommand if there is one.
+fn config_files(subcommand: &ArgMatches) -> Result<Vec<PathBuf>, RpgenError> {
+    let config_file = config_path()?;
     if !config_file.exists() {
-        debug!("Created config file {}", config_file.display());
+        write_default_config(&config_file)?;
+        info!("Created the default config file {}", config_file.display());
+    }
 
-        let string = toml::ser::to_string_pretty(&Rules::default()).unwrap();
-        let mut file = match File::create(&config_file) {
-            Ok(file) => file,
-            Err(err) => return Err((format!("Couldn't create config file {}", config_file.display()), Some(Box::new(err)))),
-        };
+    Ok([Some(config_file), pass_supplied(subcommand)?].into_iter().flatten().collect())
+}
 
-        return match file.write_all(string.as_bytes()) {
-            Ok(_) => Ok(Rules::default()),
-            Err(err) => Err((format!("Couldn't write config file {}", config_file.display()), Some(Box::new(err)))),
-        };
+/// The names of every profile in any of the config files.
+fn profile_names(config_files: &[PathBuf]) -> Result<Vec<String>, RpgenError> {
+    let mut names = Vec::new();
+    for config_file in config_files {
+        names.extend(Config::profiles(config_file)?);
     }
+    names.sort();
+    names.dedup();
+
+    Ok(names)
+}
 
-    if !config_file.is_file() {
-        return Err((format!("{} is not a file.", config_file.display()), None));
+/// Layers the config files, each with the selected profile on top if they have it,
+/// the environment variables and then the arguments over the default rules.
+fn load_config(matches: &ArgMatches, subcommand: &ArgMatches) -> Result<Config, RpgenError> {
+    let mut config = Config::default();
+    let profile = matches.value_of("PROFILE");
+
+    let config_files = config_files(subcommand)?;
+    let mut found = profile.is_none();
+    for config_file in &config_files {
+        let profile = profile.filter(|name| Config::profiles(config_file).is_ok_and(|names| names.iter().any(|n| n == name)));
+        found |= profile.is_some();
+        config.load_file(config_file, profile)?;
+        debug!("Loaded config from {}: {:?}", config_file.display(), config.rules);
+    }
+    if !found {
+        return Err(RpgenError::Validation {
+            field: "profile".to_string(),
+            reason: format!("Unknown profile {}, expected one of {:?}", profile.unwrap(), profile_names(&config_files)?),
+        });
     }
 
-    let string = match fs::read_to_string(&config_file) {
-        Ok(string) => string,
-        Err(err) => return Err((format!("Couldn't read config file {}", config_file.display()), Some(Box::new(err)))),
-    };
+    let vars = env::vars_os().filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+    config.load_env(vars)?;
 
-    let toml = match toml::from_str::<Rules>(&string) {
-        Ok(toml) => toml,
-        Err(err) => return Err((format!("Couldn't parse config file {}", config_file.display()), Some(Box::new(err)))),
-    };
+    for key in pass_args(&mut config.rules, matches)? {
+        config.set_source(key, Source::Argument);
+    }
+
+    Ok(config)
+}
+
+fn list_profiles(profiles: &ArgMatches) -> Result<(), RpgenError> {
+    let names = profile_names(&config_files(profiles)?)?;
+    if names.is_empty() {
+        info!("There are no profiles in the config files");
+    }
+    names.iter().for_each(|name| println!("{}", name));
+
+    Ok(())
+}
+
+fn run_config(matches: &ArgMatches, config: &ArgMatches) -> Result<(), RpgenError> {
+    match config.subcommand() {
+        Some(("init", init)) => {
+            let config_file = config_path()?;
+            if config_file.exists() && !init.is_present("FORCE") {
+                return Err(RpgenError::Io {
+                    path: config_file,
+                    source: io::Error::new(io::ErrorKind::AlreadyExists, "The config file already exists, use --force to overwrite it"),
+                });
+            }
 
-    return match toml.sanity_checks() {
-        Ok(_) => {
-            debug!("Loaded config from def path: {:?}", toml);
-            Ok(toml)
+            write_default_config(&config_file)?;
+            info!("Wrote the default config file {}", config_file.display());
         }
-        Err(err) => Err((err, None)),
-    };
+        Some(("show", show)) => {
+            let config = load_config(matches, show)?;
+            print!("{}", config.annotated()?);
+            if let Err(err) = config.sanity_checks() {
+                warn!("These rules are invalid: {}", err);
+            }
+        }
+        Some(("path", _)) => println!("{}", config_path()?.display()),
+        Some(("schema", _)) => println!("{}", serde_json::to_string_pretty(&schema::config_schema()).unwrap()),
+        Some(("validate", validate)) => {
+            let path = pass_supplied(validate)?.unwrap();
+            let config = |profile: Option<&str>| {
+                let mut config = Config::default();
+                config.load_file(&path, profile).map(|_| config)
+            };
+
+            // Profiles inherit the problems of the defaults, so those are only reported once.
+            let defaults = config(None)?.validate();
+            let mut problems = defaults.clone();
+            for profile in Config::profiles(&path)? {
+                let within = format!("profiles.{}", profile);
+                let config = config(Some(&profile))?;
+                // The problems with the keys of the profile already name it.
+                let keys = config.problems().iter().filter(|problem| !defaults.contains(problem)).cloned();
+                let rules = config.rules.validate().into_iter().filter(|problem| !defaults.contains(problem));
+                problems.extend(keys.chain(rules.map(|problem| problem.within(&within))));
+            }
+            if !problems.is_empty() {
+                return Err(RpgenError::Invalid(problems));
+            }
+
+            Generator::new(config(None)?.rules)?;
+            for profile in Config::profiles(&path)? {
+                Generator::new(config(Some(&profile))?.rules)?;
+            }
+            info!("{} is valid", path.display());
+        }
+        _ => unreachable!("A config subcommand is required"),
+    }
+
+    Ok(())
 }
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumIter, EnumString};
+
+/// What kind of password is generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, EnumIter, EnumString, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Mode {
+    /// Memorable passwords built from words, separators and digits.
+    #[default]
+    Words,
+    /// Fixed length passwords of random characters from the [`CharRules`](crate::chars::CharRules) classes.
+    Chars,
+    /// Numeric PINs following the [`PinRules`](crate::pin::PinRules), rejecting easily guessed ones.
+    Pin,
+    /// Memorable passwords like [`Mode::Words`] but with words built from the [`PronounceableRules`](crate::pronounceable::PronounceableRules) syllables,
+    /// so they don't appear in any dictionary. Length hints of pattern word slots are ignored.
+    Pronounceable,
+}
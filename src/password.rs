@@ -0,0 +1,98 @@
+use std::fmt::{Debug, Display, Formatter};
+
+use zeroize::Zeroize;
+
+use crate::entropy::Entropy;
+
+/// A generated password along with the words it was built from and the theoretical entropy of the rules that created it.
+///
+/// The password and its words are zeroed when dropped and are redacted from [`Debug`] and [`Display`],
+/// they can only be read through [`Password::reveal`] and [`Password::reveal_words`].
+#[derive(Clone)]
+pub struct Password {
+    value: String,
+    words: Vec<String>,
+    entropy: Entropy,
+}
+
+impl Password {
+    pub(crate) fn new(value: String, words: Vec<String>, entropy: Entropy) -> Password {
+        Password { value, words, entropy }
+    }
+
+    /// The password itself.
+    pub fn reveal(&self) -> &str {
+        &self.value
+    }
+
+    /// The transformed words in the order they appear in the password.
+    pub fn reveal_words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// The entropy of the rules this password was generated with.
+    pub fn entropy(&self) -> Entropy {
+        self.entropy
+    }
+}
+
+impl Drop for Password {
+    fn drop(&mut self) {
+        self.value.zeroize();
+        self.words.zeroize();
+    }
+}
+
+impl Debug for Password {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Password")
+            .field("value", &"[REDACTED]")
+            .field("words", &"[REDACTED]")
+            .field("entropy", &self.entropy)
+            .finish()
+    }
+}
+
+impl Display for Password {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn password() -> Password {
+        let entropy = Entropy {
+            words: 20.0,
+            ..Entropy::default()
+        };
+        Password::new("Correct-Horse-42".to_string(), vec!["Correct".to_string(), "Horse".to_string()], entropy)
+    }
+
+    #[test]
+    fn display_is_redacted() {
+        assert_eq!(password().to_string(), "[REDACTED]");
+        assert_eq!(format!("{:>12}", password()), "[REDACTED]");
+    }
+
+    #[test]
+    fn debug_is_redacted_but_keeps_the_entropy() {
+        let debug = format!("{:?}", password());
+        assert!(!debug.contains("Correct") && !debug.contains("Horse") && !debug.contains("42"), "{}", debug);
+        assert!(debug.contains(r#"value: "[REDACTED]""#) && debug.contains(r#"words: "[REDACTED]""#));
+        assert!(debug.contains("words: 20.0"));
+
+        let pretty = format!("{:#?}", password());
+        assert!(!pretty.contains("Correct") && !pretty.contains("Horse"));
+    }
+
+    #[test]
+    fn reveal_returns_the_password_and_words() {
+        let password = password();
+        assert_eq!(password.reveal(), "Correct-Horse-42");
+        assert_eq!(password.reveal_words(), ["Correct", "Horse"]);
+        assert_eq!(password.entropy().bits(), 20.0);
+    }
+}
@@ -0,0 +1,221 @@
+use std::str::FromStr;
+
+use crate::error::RpgenError;
+use crate::Transformation;
+
+/// A single part of a [`Pattern`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Slot {
+    /// Text copied into the password as is.
+    Literal(String),
+    /// A word, optionally limited to a min and max length, with its own transformation.
+    Word {
+        lengths: Option<(usize, usize)>,
+        transformation: Transformation,
+    },
+    /// A separator following the separator rules.
+    Separator,
+    /// A number of random digits.
+    Digits(usize),
+    /// A number of random symbols picked from the separator alphabet.
+    Symbols(usize),
+}
+
+/// The structure of a password, parsed from a template like `{Word}{sep}{word:4-6}{sep}{d:3}{sym}`.
+///
+/// - `{word}`, `{Word}` and `{WORD}` insert a word as is, capitalised or uppercase.
+///   A length hint can be given as `{word:5}` or `{word:4-6}` and any transformation as `{word|RANDOM}`.
+/// - `{sep}` inserts a separator following the separator rules.
+/// - `{d}` or `{d:3}` insert one or more random digits.
+/// - `{sym}` or `{sym:2}` insert one or more random symbols from the separator alphabet.
+/// - `{{` and `}}` insert a literal brace, anything else is inserted as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    /// The slots in the order they appear in the password.
+    pub slots: Vec<Slot>,
+}
+
+impl FromStr for Pattern {
+    type Err = RpgenError;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        parse(pattern).map_err(|reason| RpgenError::validation("pattern", reason))
+    }
+}
+
+fn parse(pattern: &str) -> Result<Pattern, String> {
+    let mut slots = Vec::new();
+    let mut literal = String::new();
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '}' => return Err(format!("Unmatched }} in pattern {}", pattern)),
+            '{' => {
+                let mut token = String::new();
+                let mut closed = false;
+                for char in chars.by_ref() {
+                    if char == '}' {
+                        closed = true;
+                        break;
+                    }
+                    token.push(char);
+                }
+
+                if !closed {
+                    return Err(format!("Unclosed {{ in pattern {}", pattern));
+                }
+
+                if !literal.is_empty() {
+                    slots.push(Slot::Literal(std::mem::take(&mut literal)));
+                }
+                slots.push(parse_slot(&token).map_err(|e| format!("Invalid slot {{{}}} in pattern {}: {}", token, pattern, e))?);
+            }
+            _ => literal.push(char),
+        }
+    }
+
+    if !literal.is_empty() {
+        slots.push(Slot::Literal(literal));
+    }
+
+    Ok(Pattern { slots })
+}
+
+fn parse_slot(token: &str) -> Result<Slot, String> {
+    let (head, transformation) = match token.split_once('|') {
+        Some((head, transformation)) => (head, Some(transformation)),
+        None => (token, None),
+    };
+    let (name, argument) = match head.split_once(':') {
+        Some((name, argument)) => (name.trim(), Some(argument.trim())),
+        None => (head.trim(), None),
+    };
+
+    if transformation.is_some() && !name.eq_ignore_ascii_case("word") {
+        return Err("Only word slots can have a transformation".to_string());
+    }
+
+    match name.to_lowercase().as_str() {
+        "word" => {
+            let transformation = match transformation {
+                Some(transformation) => Transformation::from_str(&transformation.trim().to_uppercase()).map_err(|_| format!("Unknown transformation {}", transformation))?,
+                None if name == "word" => Transformation::NONE,
+                None if name == "Word" => Transformation::CAPITALISE,
+                None if name == "WORD" => Transformation::UPPERCASE,
+                None => return Err("Expected word, Word or WORD".to_string()),
+            };
+            let lengths = argument.map(parse_lengths).transpose()?;
+
+            Ok(Slot::Word { lengths, transformation })
+        }
+        "sep" if argument.is_none() => Ok(Slot::Separator),
+        "d" => Ok(Slot::Digits(parse_count(argument)?)),
+        "sym" => Ok(Slot::Symbols(parse_count(argument)?)),
+        "sep" => Err("Separator slots don't take an argument".to_string()),
+        _ => Err("Expected one of word, sep, d or sym".to_string()),
+    }
+}
+
+fn parse_lengths(argument: &str) -> Result<(usize, usize), String> {
+    let (min, max) = argument.split_once('-').unwrap_or((argument, argument));
+    let min = min.trim().parse::<usize>().map_err(|_| format!("Couldn't parse {} as a length", min))?;
+    let max = max.trim().parse::<usize>().map_err(|_| format!("Couldn't parse {} as a length", max))?;
+
+    if min < 1 || min > max {
+        return Err(format!("Length range {}-{} must be at least 1 and have min less than or equal to max", min, max));
+    }
+
+    Ok((min, max))
+}
+
+fn parse_count(argument: Option<&str>) -> Result<usize, String> {
+    match argument {
+        None => Ok(1),
+        Some(count) => match count.parse::<usize>() {
+            Ok(count) if count > 0 => Ok(count),
+            _ => Err(format!("Couldn't parse {} as a count above 0", count)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slots(pattern: &str) -> Vec<Slot> {
+        Pattern::from_str(pattern).unwrap().slots
+    }
+
+    fn error(pattern: &str) -> String {
+        Pattern::from_str(pattern).unwrap_err().to_string()
+    }
+
+    fn word(lengths: Option<(usize, usize)>, transformation: Transformation) -> Slot {
+        Slot::Word { lengths, transformation }
+    }
+
+    #[test]
+    fn word_case_picks_the_transformation() {
+        assert_eq!(
+            slots("{word}{Word}{WORD}"),
+            vec![word(None, Transformation::NONE), word(None, Transformation::CAPITALISE), word(None, Transformation::UPPERCASE)]
+        );
+    }
+
+    #[test]
+    fn words_take_a_transformation_and_length_hint() {
+        assert_eq!(slots("{word|random}"), vec![word(None, Transformation::RANDOM)]);
+        assert_eq!(slots("{Word:5}"), vec![word(Some((5, 5)), Transformation::CAPITALISE)]);
+        assert_eq!(slots("{word: 4-6 | LEET}"), vec![word(Some((4, 6)), Transformation::LEET)]);
+    }
+
+    #[test]
+    fn separators_digits_and_symbols() {
+        assert_eq!(slots("{sep}{d}{d:3}{sym}{sym:2}"), vec![Slot::Separator, Slot::Digits(1), Slot::Digits(3), Slot::Symbols(1), Slot::Symbols(2)]);
+    }
+
+    #[test]
+    fn literals_and_escaped_braces() {
+        assert_eq!(
+            slots("id-{{{d:2}}}!"),
+            vec![Slot::Literal("id-{".to_string()), Slot::Digits(2), Slot::Literal("}!".to_string())]
+        );
+        assert_eq!(slots("{{}}"), vec![Slot::Literal("{}".to_string())]);
+        assert_eq!(slots(""), vec![]);
+    }
+
+    #[test]
+    fn unknown_slots_are_rejected() {
+        assert!(error("{noun}").contains("Expected one of word, sep, d or sym"));
+        assert!(error("{wOrd}").contains("Expected word, Word or WORD"));
+        assert!(error("{sep:2}").contains("Separator slots don't take an argument"));
+        assert!(error("{d|UPPERCASE}").contains("Only word slots can have a transformation"));
+        assert!(error("{word|SHOUT}").contains("Unknown transformation SHOUT"));
+    }
+
+    #[test]
+    fn bad_ranges_and_counts_are_rejected() {
+        assert!(error("{word:6-4}").contains("Length range 6-4"));
+        assert!(error("{word:0}").contains("Length range 0-0"));
+        assert!(error("{word:a-4}").contains("Couldn't parse a as a length"));
+        assert!(error("{d:0}").contains("Couldn't parse 0 as a count above 0"));
+        assert!(error("{sym:-1}").contains("Couldn't parse -1 as a count above 0"));
+    }
+
+    #[test]
+    fn unbalanced_braces_are_rejected() {
+        assert!(error("{word").contains("Unclosed {"));
+        assert!(error("{word}{sep").contains("Unclosed {"));
+        assert!(error("word}").contains("Unmatched }"));
+        assert!(matches!(Pattern::from_str("{word"), Err(RpgenError::Validation { field, .. }) if field == "pattern"));
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::asset::Asset;
+use crate::error::Problem;
+
+/// Whether a rejection rule catches the digits of a PIN.
+type Check = fn(&[u32]) -> bool;
+
+/// The rules for [`Mode::Pin`](crate::mode::Mode::Pin) numeric PINs, weak PINs are regenerated like any policy violation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PinRules {
+    /// The number of digits in each PIN.
+    pub length: usize,
+    /// Whether PINs counting up or down by the same step, like `1234`, `9876` or `7890`, are rejected.
+    pub reject_sequences: bool,
+    /// Whether PINs made of a repeated block, like `1111`, `1212` or `123123`, are rejected.
+    pub reject_repeats: bool,
+    /// Whether PINs which read the same backwards, like `1221`, are rejected.
+    pub reject_palindromes: bool,
+    /// Whether PINs which look like a date or year, like `0412`, `1987` or `250399`, are rejected.
+    ///
+    /// Only PINs of 4, 6 or 8 digits can look like a date, any other length is never rejected by this rule.
+    pub reject_dates: bool,
+    /// Whether PINs from the bundled list of most common PINs are rejected.
+    pub reject_common: bool,
+}
+
+impl PinRules {
+    /// The names of every rejection rule the PIN is caught by.
+    pub fn weaknesses(&self, pin: &str) -> Vec<&'static str> {
+        let digits = digits(pin);
+        let mut weaknesses = self.patterns(&digits).collect::<Vec<_>>();
+
+        if self.reject_common && common_pins().contains(pin) {
+            weaknesses.push("reject_common");
+        }
+
+        weaknesses
+    }
+
+    /// The names of every rejection rule other than `reject_common` the digits are caught by.
+    fn patterns<'a>(&'a self, digits: &'a [u32]) -> impl Iterator<Item = &'static str> + 'a {
+        let rules: [(&'static str, bool, Check); 4] = [
+            ("reject_sequences", self.reject_sequences, is_sequence),
+            ("reject_repeats", self.reject_repeats, is_repeat),
+            ("reject_palindromes", self.reject_palindromes, is_palindrome),
+            ("reject_dates", self.reject_dates, is_date),
+        ];
+
+        rules.into_iter().filter(move |(_, enabled, check)| *enabled && check(digits)).map(|(name, _, _)| name)
+    }
+
+    /// The number of PINs made only of digits from the pool which no rule rejects.
+    ///
+    /// Up to 8 digits this is exact, since only the PINs which some rule could reject are built and checked.
+    /// Longer PINs can't be dates or common PINs and there are too many of the rest to build,
+    /// so each rule is assumed to reject every PIN it could on its own, which makes the count a lower bound.
+    pub fn accepted(&self, pool: &[char]) -> u64 {
+        let pool = pool.iter().filter_map(|c| c.to_digit(10)).collect::<Vec<_>>();
+        let length = self.length as u32;
+        let total = (pool.len() as u64).pow(length);
+        if self.length > 8 {
+            let sequences = self.reject_sequences as u64 * 90;
+            let repeats = (1..length).filter(|size| self.reject_repeats && length.is_multiple_of(*size)).map(|size| (pool.len() as u64).pow(size)).sum::<u64>();
+            let palindromes = self.reject_palindromes as u64 * (pool.len() as u64).pow(length.div_ceil(2));
+            return total.saturating_sub(sequences + repeats + palindromes);
+        }
+
+        let common = common_pins().iter().filter(|pin| self.reject_common && pin.len() == self.length).filter_map(|pin| pin.parse().ok()).collect::<HashSet<u64>>();
+
+        let mut candidates = common.iter().copied().collect::<Vec<_>>();
+        if self.reject_sequences {
+            candidates.extend((0..10).flat_map(|start| (1..10).map(move |step| (0..length).fold(0, |pin, i| pin * 10 + ((start + i * step) % 10) as u64))));
+        }
+        if self.reject_repeats {
+            for size in (1..length).filter(|size| length.is_multiple_of(*size)) {
+                candidates.extend((0..10u64.pow(size)).map(|block| (0..length / size).fold(0, |pin, _| pin * 10u64.pow(size) + block)));
+            }
+        }
+        if self.reject_palindromes {
+            let half = length.div_ceil(2);
+            candidates.extend((0..10u64.pow(half)).map(|front| {
+                let back = front / 10u64.pow(half - length / 2);
+                let reversed = (0..length / 2).fold((0, back), |(reversed, rest), _| (reversed * 10 + rest % 10, rest / 10)).0;
+                front * 10u64.pow(length / 2) + reversed
+            }));
+        }
+        if self.reject_dates {
+            candidates.extend(date_candidates(self.length));
+        }
+
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let mut digits = vec![0; self.length];
+        let rejected = candidates
+            .into_iter()
+            .filter(|pin| {
+                let mut rest = *pin;
+                for digit in digits.iter_mut().rev() {
+                    *digit = (rest % 10) as u32;
+                    rest /= 10;
+                }
+                digits.iter().all(|digit| pool.contains(digit)) && (common.contains(pin) || self.patterns(&digits).next().is_some())
+            })
+            .count();
+
+        total - rejected as u64
+    }
+
+    /// Every problem with the PIN rules.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        if self.length < 4 || self.length > 12 {
+            problems.push(Problem::new(
+                "pin.length",
+                format!("Pin length must be within bounds of 4 and 12, received {}", self.length),
+                "Set it to a number from 4 to 12",
+            ));
+        }
+
+        problems
+    }
+}
+
+impl Default for PinRules {
+    fn default() -> Self {
+        PinRules {
+            length: 6,
+            reject_sequences: true,
+            reject_repeats: true,
+            reject_palindromes: true,
+            reject_dates: true,
+            reject_common: true,
+        }
+    }
+}
+
+/// The bundled list of most common PINs, loaded once.
+fn common_pins() -> &'static HashSet<String> {
+    static COMMON_PINS: OnceLock<HashSet<String>> = OnceLock::new();
+    COMMON_PINS.get_or_init(|| match Asset::get("common_pins.txt") {
+        Some(asset) => String::from_utf8_lossy(asset.data.as_ref()).lines().map(|line| line.trim().to_string()).collect(),
+        None => HashSet::new(),
+    })
+}
+
+/// The digits of a PIN.
+fn digits(pin: &str) -> Vec<u32> {
+    pin.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+/// Every PIN of the length which could look like a date, which includes impossible days like the 31st of February.
+fn date_candidates(length: usize) -> Vec<u64> {
+    let days = (1..=31).flat_map(|day| (1..=12).map(move |month| (day, month)));
+    let years = 1900..=2099u64;
+
+    match length {
+        4 => years.chain(days.flat_map(|(day, month)| [day * 100 + month, month * 100 + day])).collect(),
+        6 => days
+            .flat_map(|(day, month)| (0..100).flat_map(move |n| [(day * 100 + month) * 100 + n, (month * 100 + day) * 100 + n, n * 10000 + month * 100 + day]))
+            .collect(),
+        8 => days
+            .flat_map(|(day, month)| years.clone().flat_map(move |year| [(day * 100 + month) * 10000 + year, (month * 100 + day) * 10000 + year, year * 10000 + month * 100 + day]))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Every digit differs from the one before by the same non zero step, wrapping around from 9 to 0.
+fn is_sequence(digits: &[u32]) -> bool {
+    let mut steps = digits.windows(2).map(|pair| (pair[1] + 10 - pair[0]) % 10);
+    match steps.next() {
+        Some(0) | None => false,
+        Some(step) => steps.all(|s| s == step),
+    }
+}
+
+/// The digits read the same backwards.
+fn is_palindrome(digits: &[u32]) -> bool {
+    digits.iter().eq(digits.iter().rev())
+}
+
+/// The digits are a shorter block repeated, which includes a single digit repeated.
+fn is_repeat(digits: &[u32]) -> bool {
+    (1..digits.len()).filter(|size| digits.len().is_multiple_of(*size)).any(|size| digits.chunks(size).all(|chunk| chunk == &digits[..size]))
+}
+
+/// The digits read as a year, or a day and month in any common order with an optional two or four digit year.
+fn is_date(digits: &[u32]) -> bool {
+    let number = |range: std::ops::Range<usize>| digits[range].iter().fold(0, |n, d| n * 10 + d);
+    let day_month = |day: u32, month: u32| (1..=12).contains(&month) && day >= 1 && day <= days_in_month(month);
+    let year = |year: u32| (1900..=2099).contains(&year);
+
+    match digits.len() {
+        4 => year(number(0..4)) || day_month(number(0..2), number(2..4)) || day_month(number(2..4), number(0..2)),
+        6 => day_month(number(0..2), number(2..4)) || day_month(number(2..4), number(0..2)) || day_month(number(4..6), number(2..4)),
+        8 => {
+            (year(number(4..8)) && (day_month(number(0..2), number(2..4)) || day_month(number(2..4), number(0..2))))
+                || (year(number(0..4)) && day_month(number(6..8), number(4..6)))
+        }
+        _ => false,
+    }
+}
+
+fn days_in_month(month: u32) -> u32 {
+    match month {
+        2 => 29,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequences_step_by_the_same_amount() {
+        for pin in ["1234", "9876", "7890", "2468", "135791"] {
+            assert!(is_sequence(&digits(pin)), "{} is a sequence", pin);
+        }
+        for pin in ["1111", "1243", "123457"] {
+            assert!(!is_sequence(&digits(pin)), "{} isn't a sequence", pin);
+        }
+    }
+
+    #[test]
+    fn repeats_are_a_repeated_block() {
+        for pin in ["1111", "1212", "123123", "121212", "12341234"] {
+            assert!(is_repeat(&digits(pin)), "{} is a repeat", pin);
+        }
+        for pin in ["1213", "123124", "12312"] {
+            assert!(!is_repeat(&digits(pin)), "{} isn't a repeat", pin);
+        }
+    }
+
+    #[test]
+    fn palindromes_read_the_same_backwards() {
+        assert!(is_palindrome(&digits("1221")));
+        assert!(is_palindrome(&digits("12321")));
+        assert!(!is_palindrome(&digits("1223")));
+    }
+
+    #[test]
+    fn dates_are_only_found_in_4_6_and_8_digits() {
+        for pin in ["0412", "1987", "2099", "250399", "120425", "990425", "25031999", "03251999", "19990325"] {
+            assert!(is_date(&digits(pin)), "{} is a date", pin);
+        }
+        for pin in ["1899", "3213", "320199", "321399", "32011999"] {
+            assert!(!is_date(&digits(pin)), "{} isn't a date", pin);
+        }
+        // Dates aren't looked for in any other length.
+        assert!(!is_date(&digits("04125")));
+        assert!(!is_date(&digits("0412199")));
+    }
+
+    #[test]
+    fn weaknesses_name_every_rule() {
+        let rules = PinRules::default();
+        // The 11th of November.
+        assert_eq!(rules.weaknesses("1111"), vec!["reject_repeats", "reject_palindromes", "reject_dates", "reject_common"]);
+        assert_eq!(rules.weaknesses("123456"), vec!["reject_sequences", "reject_common"]);
+        assert!(rules.weaknesses("583920").is_empty());
+
+        let lenient = PinRules {
+            reject_repeats: false,
+            reject_palindromes: false,
+            reject_common: false,
+            ..rules
+        };
+        assert_eq!(lenient.weaknesses("1111"), vec!["reject_dates"]);
+        assert!(lenient.weaknesses("1192").is_empty());
+    }
+
+    /// Counts the accepted PINs by checking every one of them.
+    fn brute_force(rules: &PinRules, pool: &[char]) -> u64 {
+        (0..10u64.pow(rules.length as u32))
+            .map(|n| format!("{:0length$}", n, length = rules.length))
+            .filter(|pin| pin.chars().all(|c| pool.contains(&c)))
+            .filter(|pin| rules.weaknesses(pin).is_empty())
+            .count() as u64
+    }
+
+    #[test]
+    fn accepted_pins_are_counted_exactly() {
+        let digits = ('0'..='9').collect::<Vec<_>>();
+        let unambiguous = ('2'..='9').collect::<Vec<_>>();
+        for length in [4, 5] {
+            let rules = PinRules { length, ..PinRules::default() };
+            assert_eq!(rules.accepted(&digits), brute_force(&rules, &digits), "{} digits", length);
+            assert_eq!(rules.accepted(&unambiguous), brute_force(&rules, &unambiguous), "{} unambiguous digits", length);
+        }
+
+        let rules = PinRules { length: 6, ..PinRules::default() };
+        assert_eq!(rules.accepted(&unambiguous), brute_force(&rules, &unambiguous));
+    }
+
+    #[test]
+    fn accepted_pins_above_8_digits_are_a_lower_bound() {
+        let digits = ('0'..='9').collect::<Vec<_>>();
+        let rules = PinRules { length: 9, ..PinRules::default() };
+        // 90 sequences, 10 repeats of one digit, 1000 repeats of three digits and 100000 palindromes.
+        assert_eq!(rules.accepted(&digits), 1_000_000_000 - 90 - 10 - 1000 - 100_000);
+    }
+}
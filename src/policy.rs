@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::Problem;
+
+/// Requirements every generated password must meet, passwords which don't are regenerated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// The minimum number of characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    /// The maximum number of characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// Whether at least one uppercase letter is required.
+    pub require_uppercase: bool,
+    /// Whether at least one lowercase letter is required.
+    pub require_lowercase: bool,
+    /// Whether at least one digit is required.
+    pub require_digit: bool,
+    /// Whether at least one symbol is required, which is anything other than a letter, digit or whitespace.
+    pub require_symbol: bool,
+    /// The most times the same character may repeat in a row.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_repeating: Option<usize>,
+    /// How many times a password is regenerated before giving up.
+    pub max_attempts: usize,
+}
+
+impl Policy {
+    /// The names of every requirement the password doesn't meet.
+    pub fn violations(&self, password: &str) -> Vec<&'static str> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+
+        if self.min_length.is_some_and(|min| length < min) {
+            violations.push("min_length");
+        }
+
+        if self.max_length.is_some_and(|max| length > max) {
+            violations.push("max_length");
+        }
+
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            violations.push("require_uppercase");
+        }
+
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            violations.push("require_lowercase");
+        }
+
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push("require_digit");
+        }
+
+        if self.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            violations.push("require_symbol");
+        }
+
+        if let Some(max) = self.max_repeating {
+            let chars = password.chars().collect::<Vec<_>>();
+            if chars.chunk_by(|a, b| a == b).any(|run| run.len() > max) {
+                violations.push("max_repeating");
+            }
+        }
+
+        violations
+    }
+
+    /// Whether the policy has any requirement, so it can reject a password at all.
+    pub fn is_active(&self) -> bool {
+        *self != Policy { max_attempts: self.max_attempts, ..Policy::default() }
+    }
+
+    /// Every problem which stops the policy from being met.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        if let (Some(min), Some(max)) = (self.min_length, self.max_length) {
+            if min > max {
+                problems.push(Problem::new(
+                    "policy.min_length",
+                    format!("Policy min length must be less than or equal to max length, received {} and {}", min, max),
+                    format!("Set it to at most {} or raise the max length", max),
+                ));
+            }
+        }
+
+        if self.max_repeating == Some(0) {
+            problems.push(Problem::new("policy.max_repeating", "Policy max repeating must be at least 1", "Set it to 1 or more, or remove it"));
+        }
+
+        if self.max_attempts < 1 {
+            problems.push(Problem::new("policy.max_attempts", "Policy max attempts must be at least 1", "Set it to 1 or more"));
+        }
+
+        problems
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            min_length: None,
+            max_length: None,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+            max_repeating: None,
+            max_attempts: 1000,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn violations_name_every_failed_requirement() {
+        let policy = Policy {
+            min_length: Some(8),
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            max_repeating: Some(2),
+            ..Policy::default()
+        };
+
+        assert_eq!(policy.violations("Abc-1234"), Vec::<&str>::new());
+        assert_eq!(policy.violations("abccc"), ["min_length", "require_uppercase", "require_digit", "require_symbol", "max_repeating"]);
+        assert_eq!(Policy { max_length: Some(4), ..Policy::default() }.violations("straße"), ["max_length"]);
+    }
+
+    #[test]
+    fn unmeetable_policies_are_rejected() {
+        let policy = Policy {
+            min_length: Some(10),
+            max_length: Some(8),
+            max_repeating: Some(0),
+            max_attempts: 0,
+            ..Policy::default()
+        };
+        let fields = policy.validate().into_iter().map(|problem| problem.field).collect::<Vec<_>>();
+
+        assert_eq!(fields, ["policy.min_length", "policy.max_repeating", "policy.max_attempts"]);
+        assert!(Policy::default().validate().is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ambiguous;
+use crate::error::Problem;
+
+/// The rules for [`Mode::Pronounceable`](crate::mode::Mode::Pronounceable) words made up of syllables instead of picked from a word list.
+///
+/// Each syllable is an onset followed by a vowel, ending in a coda with a chance of [`coda_probability`](PronounceableRules::coda_probability).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PronounceableRules {
+    /// The minimum number of syllables in each word.
+    pub min_syllables: usize,
+    /// The maximum number of syllables in each word.
+    pub max_syllables: usize,
+    /// The consonants which start a syllable.
+    pub onsets: Vec<String>,
+    /// The vowels in the middle of a syllable.
+    pub vowels: Vec<String>,
+    /// The consonants which can end a syllable.
+    pub codas: Vec<String>,
+    /// The chance of a syllable ending in a coda, between 0 and 1.
+    pub coda_probability: f64,
+}
+
+impl PronounceableRules {
+    /// A copy of the rules without any phonemes which could contain an [ambiguous](crate::ambiguous::AMBIGUOUS) character.
+    pub fn without_ambiguous(&self) -> PronounceableRules {
+        let filter = |table: &[String]| table.iter().filter(|p| !ambiguous::could_be_ambiguous(p)).cloned().collect();
+
+        PronounceableRules {
+            onsets: filter(&self.onsets),
+            vowels: filter(&self.vowels),
+            codas: filter(&self.codas),
+            ..self.clone()
+        }
+    }
+
+    /// Every problem which stops syllables from being built from the tables.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        for (field, syllables) in [("min_syllables", self.min_syllables), ("max_syllables", self.max_syllables)] {
+            if !(1..=8).contains(&syllables) {
+                problems.push(Problem::new(
+                    format!("pronounceable.{}", field),
+                    format!("Pronounceable syllables must be within bounds of 1 and 8, received {}", syllables),
+                    "Set it to a number from 1 to 8",
+                ));
+            }
+        }
+
+        if self.min_syllables > self.max_syllables {
+            problems.push(Problem::new(
+                "pronounceable.min_syllables",
+                format!(
+                    "Pronounceable min syllables must be less than or equal to max syllables, received {} and {}",
+                    self.min_syllables, self.max_syllables
+                ),
+                format!("Set it to at most {} or raise the max syllables", self.max_syllables),
+            ));
+        }
+
+        for (field, table) in [("onsets", &self.onsets), ("vowels", &self.vowels)] {
+            if table.is_empty() {
+                problems.push(Problem::new(
+                    format!("pronounceable.{}", field),
+                    format!("Pronounceable {} must not be empty", field),
+                    "Add at least one phoneme, or remove it to use the defaults",
+                ));
+            }
+        }
+
+        for (field, table) in [("onsets", &self.onsets), ("vowels", &self.vowels), ("codas", &self.codas)] {
+            if table.iter().any(|phoneme| phoneme.trim().is_empty()) {
+                problems.push(Problem::new(
+                    format!("pronounceable.{}", field),
+                    format!("Pronounceable {} must not contain blank entries", field),
+                    "Remove the blank entries",
+                ));
+            }
+        }
+
+        if !(0.0..=1.0).contains(&self.coda_probability) {
+            problems.push(Problem::new(
+                "pronounceable.coda_probability",
+                format!("Pronounceable coda probability must be within bounds of 0 and 1, received {}", self.coda_probability),
+                "Set it to a number from 0 to 1",
+            ));
+        }
+
+        if self.coda_probability > 0.0 && self.codas.is_empty() {
+            problems.push(Problem::new(
+                "pronounceable.codas",
+                "Pronounceable codas must not be empty when the coda probability is above 0",
+                "Add at least one coda or set the coda probability to 0",
+            ));
+        }
+
+        problems
+    }
+}
+
+impl Default for PronounceableRules {
+    fn default() -> Self {
+        let table = |phonemes: &[&str]| phonemes.iter().map(|p| p.to_string()).collect();
+
+        PronounceableRules {
+            min_syllables: 2,
+            max_syllables: 3,
+            onsets: table(&[
+                "b", "c", "d", "f", "g", "h", "j", "k", "l", "m", "n", "p", "r", "s", "t", "v", "w", "z", "bl", "br", "ch", "cl", "cr", "dr",
+                "fl", "fr", "gl", "gr", "pl", "pr", "sh", "st", "th", "tr",
+            ]),
+            vowels: table(&["a", "e", "i", "o", "u", "ai", "ea", "ee", "io", "ou"]),
+            codas: table(&["l", "m", "n", "r", "s", "t", "nd", "ng", "rk", "sh", "st"]),
+            coda_probability: 0.3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{Generator, Mode, Rules, Separator, Transformation};
+
+    fn rules() -> PronounceableRules {
+        let table = |phonemes: &[&str]| phonemes.iter().map(|p| p.to_string()).collect();
+
+        PronounceableRules {
+            min_syllables: 2,
+            max_syllables: 3,
+            onsets: table(&["b", "tr"]),
+            vowels: table(&["a", "ou"]),
+            codas: table(&["n", "st"]),
+            coda_probability: 0.5,
+        }
+    }
+
+    /// The number of syllables the word is made of, trying every way of splitting it into phonemes.
+    fn syllables(word: &str, rules: &PronounceableRules) -> Option<usize> {
+        if word.is_empty() {
+            return Some(0);
+        }
+
+        fn strip<'a>(word: &'a str, table: &[String]) -> Vec<&'a str> {
+            table.iter().filter_map(|phoneme| word.strip_prefix(phoneme.as_str())).collect()
+        }
+
+        for rest in strip(word, &rules.onsets).into_iter().flat_map(|rest| strip(rest, &rules.vowels)) {
+            let endings = std::iter::once(rest).chain(strip(rest, &rules.codas));
+            if let Some(count) = endings.filter_map(|rest| syllables(rest, rules)).next() {
+                return Some(count + 1);
+            }
+        }
+
+        None
+    }
+
+    #[test]
+    fn words_are_built_from_syllables() {
+        let rules = Rules {
+            mode: Mode::Pronounceable,
+            words: 2,
+            digits_after: 0,
+            separator_char: Separator::Fixed('-'),
+            transform: Transformation::NONE,
+            pronounceable: rules(),
+            amount: 100,
+            ..Rules::default()
+        };
+
+        let mut counts = Vec::new();
+        for password in Generator::with_rng(rules, StdRng::seed_from_u64(2)).unwrap().generate().unwrap() {
+            assert_eq!(password.reveal_words().len(), 2);
+            for word in password.reveal_words() {
+                let count = syllables(word, &self::rules()).unwrap_or_else(|| panic!("{} isn't made of syllables", word));
+                assert!((2..=3).contains(&count), "{} has {} syllables", word, count);
+                counts.push(count);
+            }
+        }
+        assert!(counts.contains(&2) && counts.contains(&3));
+    }
+
+    #[test]
+    fn tables_need_phonemes() {
+        let rules = PronounceableRules {
+            vowels: Vec::new(),
+            codas: vec![" ".to_string()],
+            min_syllables: 4,
+            ..rules()
+        };
+        let fields = rules.validate().into_iter().map(|problem| problem.field).collect::<Vec<_>>();
+
+        assert_eq!(fields, ["pronounceable.min_syllables", "pronounceable.vowels", "pronounceable.codas"]);
+        assert!(self::rules().validate().is_empty());
+    }
+}
@@ -1,40 +1,336 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt::{Debug, Formatter};
+use std::path::Path;
+use std::str::FromStr;
 
-#[derive(Serialize, Deserialize)]
+use crate::ambiguous;
+use crate::ascii;
+use crate::chars::CharRules;
+use crate::config::Config;
+use crate::error::{Problem, RpgenError};
+use crate::mode::Mode;
+use crate::pattern::{Pattern, Slot};
+use crate::pin::PinRules;
+use crate::policy::Policy;
+use crate::pronounceable::PronounceableRules;
+use crate::separator::Separator;
+use crate::transformation::Transformation;
+use crate::wordlist::{WordList, BUILTIN, LANGUAGES};
+
+/// The set of rules a [`Generator`](crate::Generator) follows when creating passwords.
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Rules {
+    /// The number of words in each password.
     pub words: usize,
+    /// The minimum length of each word, in characters before the transformation,
+    /// which can change the length of non-ASCII words like `straße` becoming `STRASSE`.
     pub min_length: usize,
+    /// The maximum length of each word.
     pub max_length: usize,
-    pub transform: Box<str>,
-    pub separator_char: Box<str>,
+    /// The transformation applied to each word.
+    pub transform: Transformation,
+    /// What is placed between words.
+    pub separator_char: Separator,
+    /// The characters a random separator is picked from.
     pub separator_alphabet: Box<str>,
+    /// Whether a random separator is reused for every separator within a password.
     pub match_random_char: bool,
+    /// The number of digits before the words.
     pub digits_before: usize,
+    /// The number of digits after the words.
     pub digits_after: usize,
+    /// The number of passwords to generate.
     pub amount: usize,
+    /// The name of a [built-in](crate::wordlist::BUILTIN) word list,
+    /// or a path to a custom word list which is either newline delimited or JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wordlist: Option<Box<str>>,
+    /// The ISO 639-1 code of the [language](crate::wordlist::LANGUAGES) whose embedded word list is used when there is no `wordlist`.
+    pub language: Box<str>,
+    /// Whether words are picked uniformly from the whole word list, ignoring the min and max length.
+    pub uniform_words: bool,
+    /// Whether passwords are limited to characters which can be typed on any keyboard.
+    pub ascii_only: bool,
+    /// Whether non-ASCII words are transliterated, like `é` to `e` and `ü` to `ue`, instead of dropped when `ascii_only` is set.
+    pub transliterate: bool,
+    /// Whether characters which are easily mistaken for one another, like `l`, `1` and `I`, are left out of passwords.
+    pub exclude_ambiguous: bool,
+    /// A [`Pattern`] describing the structure of each password,
+    /// replacing the words, transform and digit rules.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<Box<str>>,
+    /// What kind of password is generated.
+    pub mode: Mode,
+    /// The chance of each character being substituted by [`Transformation::LEET_RANDOM`].
+    pub leet_probability: f64,
+    /// The substitutions made by the leet transformations, keyed by a single lowercase character.
+    pub leet_table: BTreeMap<String, String>,
+    /// The requirements every password must meet.
+    pub policy: Policy,
+    /// The rules for random character passwords, used when the mode is [`Mode::Chars`].
+    pub chars: CharRules,
+    /// The rules for numeric PINs, used when the mode is [`Mode::Pin`].
+    pub pin: PinRules,
+    /// The rules for building words out of syllables, used when the mode is [`Mode::Pronounceable`].
+    pub pronounceable: PronounceableRules,
 }
 
 impl Rules {
-    pub fn sanity_checks(&self) -> Result<(), String> {
-        if self.words < 1 || self.words > 10 {
-            return Err(format!("Words must be within bounds of 1 and 10, received {}", self.words));
-        }
+    /// Reads and checks the rules from a TOML, JSON or YAML config file, any missing rules use their default value.
+    pub fn from_file(path: &Path) -> Result<Rules, RpgenError> {
+        Rules::from_profile(path, None)
+    }
+
+    /// Reads and checks the rules from a TOML, JSON or YAML config file with one of its [profiles](crate::Config::load_file) on top.
+    pub fn from_profile(path: &Path, profile: Option<&str>) -> Result<Rules, RpgenError> {
+        let mut config = Config::default();
+        config.load_file(path, profile)?;
+
+        config.sanity_checks()?;
+        Ok(config.rules)
+    }
+
+    /// Checks that the rules are within the bounds the generator supports, failing with every [problem](Rules::validate) at once.
+    pub fn sanity_checks(&self) -> Result<(), RpgenError> {
+        RpgenError::check(self.validate())
+    }
+
+    /// Every problem with the rules, each with the path to the rule and a suggested fix.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
 
-        if self.min_length < 3 || self.min_length > 9 {
-            return Err(format!("Min length must be within bounds of 3 and 9, received {}", self.min_length));
+        let bounds = [
+            ("words", "Words", self.words, 1, 10),
+            ("digits_before", "Digits before", self.digits_before, 0, 10),
+            ("digits_after", "Digits after", self.digits_after, 0, 10),
+            ("amount", "Amount", self.amount, 1, 10000),
+        ];
+        for (field, name, value, min, max) in bounds {
+            if value < min || value > max {
+                problems.push(Problem::new(
+                    field,
+                    format!("{} must be within bounds of {} and {}, received {}", name, min, max, value),
+                    format!("Set it to a number from {} to {}", min, max),
+                ));
+            }
         }
 
-        if self.max_length < 3 || self.max_length > 9 {
-            return Err(format!("Max length must be within bounds of 3 and 9, received {}", self.max_length));
+        // The upper bound depends on the word list, which is checked once it's loaded by the word list problems.
+        if self.min_length < 1 {
+            problems.push(Problem::new("min_length", "Min length must be at least 1, received 0", "Set it to 1 or more"));
         }
 
         if self.min_length > self.max_length {
-            return Err(format!("Min length must be less than or equal to max length, received {}", self.max_length));
+            problems.push(Problem::new(
+                "min_length",
+                format!("Min length must be less than or equal to max length, received {} and {}", self.min_length, self.max_length),
+                format!("Set it to at most {} or raise the max length", self.max_length),
+            ));
+        }
+
+        if let Some(wordlist) = self.wordlist.as_deref().filter(|_| self.mode == Mode::Words) {
+            if !BUILTIN.iter().any(|(name, _)| name.eq_ignore_ascii_case(wordlist)) && !Path::new(wordlist).exists() {
+                let names = BUILTIN.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                problems.push(Problem::new(
+                    "wordlist",
+                    format!("{} is neither a built-in word list nor an existing file", wordlist),
+                    format!("Set it to one of {:?} or the path of a word list", names),
+                ));
+            }
+        }
+
+        if !LANGUAGES.iter().any(|(code, _)| code.eq_ignore_ascii_case(&self.language)) {
+            let codes = LANGUAGES.iter().map(|(code, _)| *code).collect::<Vec<_>>();
+            problems.push(Problem::new(
+                "language",
+                format!("Unknown language {}", self.language),
+                format!("Set it to one of {:?}", codes),
+            ));
+        }
+
+        if let Some(char) = self.separator_alphabet.chars().find(|c| c.is_control()) {
+            problems.push(Problem::new(
+                "separator_alphabet",
+                format!("Separator alphabet must not contain control characters, received {:?}", char),
+                "Remove the control characters",
+            ));
+        }
+
+        let pattern = match self.pattern.as_deref().map(Pattern::from_str) {
+            Some(Err(err)) => {
+                problems.push(Problem::new("pattern", err.to_string(), "Check the placeholders against the patterns section of the README"));
+                None
+            }
+            pattern => pattern.and_then(Result::ok),
+        };
+
+        let separators = match &pattern {
+            Some(pattern) => pattern.slots.iter().any(|slot| matches!(slot, Slot::Separator)),
+            None => self.words > 1 || self.digits_before > 0 || self.digits_after > 0,
+        };
+        let symbols = pattern.iter().flat_map(|p| &p.slots).any(|slot| matches!(slot, Slot::Symbols(_)));
+        let uses_words = matches!(self.mode, Mode::Words | Mode::Pronounceable);
+        if uses_words && ((separators && self.separator_char == Separator::Random) || symbols) && self.separator_pool().is_empty() {
+            problems.push(Problem::new(
+                "separator_alphabet",
+                "Separator alphabet has no characters to pick random separators or symbols from",
+                match self.exclude_ambiguous {
+                    true => "Add some characters which aren't ambiguous, or set the separator char",
+                    false => "Add some characters, or set the separator char",
+                },
+            ));
+        }
+
+        if let Some(char) = self.separator_char.chars().iter().find(|c| c.is_control()) {
+            problems.push(Problem::new(
+                "separator_char",
+                format!("Separator char must not contain control characters, received {:?}", char),
+                "Remove the control characters",
+            ));
+        }
+
+        if let Some(char) = self.separator_char.chars().iter().find(|c| self.exclude_ambiguous && ambiguous::is_ambiguous(**c)) {
+            problems.push(Problem::new(
+                "separator_char",
+                format!("Separator char {} is ambiguous, which is excluded by exclude ambiguous", char),
+                format!("Use a character other than {:?}", ambiguous::AMBIGUOUS),
+            ));
+        }
+
+        if self.ascii_only {
+            let mut texts = vec![("separator_alphabet".to_string(), self.separator_alphabet.to_string())];
+            texts.push(("separator_char".to_string(), self.separator_char.chars().iter().collect()));
+            for slot in pattern.iter().flat_map(|p| &p.slots) {
+                if let Slot::Literal(text) = slot {
+                    texts.push(("pattern".to_string(), text.clone()));
+                }
+            }
+            texts.extend(self.leet_table.iter().map(|(key, value)| (format!("leet_table.{}", key), value.clone())));
+            if self.mode == Mode::Chars {
+                texts.push(("chars.symbols".to_string(), self.chars.symbols.clone()));
+                texts.push(("chars.custom".to_string(), self.chars.custom.clone()));
+            }
+            if self.mode == Mode::Pronounceable {
+                let tables = [("onsets", &self.pronounceable.onsets), ("vowels", &self.pronounceable.vowels), ("codas", &self.pronounceable.codas)];
+                texts.extend(tables.iter().flat_map(|(field, table)| table.iter().map(move |p| (format!("pronounceable.{}", field), p.clone()))));
+            }
+
+            for (field, text) in texts {
+                if let Some(char) = text.chars().find(|c| !ascii::is_keyboard_safe(*c)) {
+                    problems.push(Problem::new(
+                        field.as_str(),
+                        format!("{} must only contain printable ASCII characters when ascii only is set, received {:?}", field, char),
+                        "Remove the other characters or turn off ascii only",
+                    ));
+                }
+            }
+        }
+
+        if !(0.0..=1.0).contains(&self.leet_probability) {
+            problems.push(Problem::new(
+                "leet_probability",
+                format!("Leet probability must be within bounds of 0 and 1, received {}", self.leet_probability),
+                "Set it to a number from 0 to 1",
+            ));
+        }
+
+        for key in self.leet_table.keys() {
+            if key.chars().count() != 1 {
+                problems.push(Problem::new(
+                    format!("leet_table.{}", key),
+                    format!("Leet table keys must be a single character, received {}", key),
+                    "Add a separate entry for each character",
+                ));
+            } else if key.to_lowercase() != *key {
+                problems.push(Problem::new(
+                    format!("leet_table.{}", key),
+                    format!("Leet table keys must be lowercase, received {}", key),
+                    format!("Use {} instead, which also substitutes {}", key.to_lowercase(), key),
+                ));
+            }
+        }
+
+        problems.extend(self.policy.validate());
+
+        match self.mode {
+            Mode::Chars => problems.extend(self.chars.validate(self.exclude_ambiguous)),
+            Mode::Pin => problems.extend(self.pin.validate()),
+            Mode::Pronounceable => problems.extend(self.syllables().validate()),
+            Mode::Words => {}
+        }
+
+        problems
+    }
+
+    /// Every problem with the word lengths, which must each select at least one word out of the word list the generator picks from.
+    pub fn validate_words(&self, words: &WordList) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        if self.mode != Mode::Words {
+            return problems;
         }
 
-        Ok(())
+        let lengths = words.lengths(1, usize::MAX);
+        let available = match (lengths.first(), lengths.last()) {
+            (Some(shortest), Some(longest)) => format!("the {} to {} characters the word list has", shortest, longest),
+            _ => return vec![Problem::new("wordlist", "The word list has no words", "Use a word list with some words in it")],
+        };
+
+        if self.pattern.is_none() && !self.uniform_words && words.lengths(self.min_length, self.max_length).is_empty() {
+            problems.push(Problem::new(
+                "min_length",
+                format!("The word list has no words between {} and {} characters long", self.min_length, self.max_length),
+                format!("Set the min and max length within {}", available),
+            ));
+        }
+
+        for slot in self.pattern.as_deref().and_then(|pattern| Pattern::from_str(pattern).ok()).iter().flat_map(|p| p.slots.clone()) {
+            match slot {
+                Slot::Word { lengths: Some((min, max)), .. } if words.lengths(min, max).is_empty() => problems.push(Problem::new(
+                    "pattern",
+                    format!("The word list has no words between {} and {} characters long for {{word:{}-{}}}", min, max, min, max),
+                    format!("Set the length hint within {}", available),
+                )),
+                Slot::Word { lengths: None, .. } if !self.uniform_words && words.lengths(self.min_length, self.max_length).is_empty() => {
+                    problems.push(Problem::new(
+                        "min_length",
+                        format!("The word list has no words between {} and {} characters long", self.min_length, self.max_length),
+                        format!("Set the min and max length within {}", available),
+                    ))
+                }
+                _ => {}
+            }
+        }
+        problems.dedup();
+
+        problems
+    }
+
+    /// The leet substitution for the character, matched case insensitively.
+    pub fn leet_substitution(&self, char: char) -> Option<&str> {
+        self.leet_table
+            .get(&char.to_lowercase().to_string())
+            .map(String::as_str)
+            .filter(|substitution| !self.exclude_ambiguous || !substitution.chars().any(ambiguous::is_ambiguous))
+    }
+
+    /// The characters a random separator or symbol is picked from, without ambiguous characters if they're excluded.
+    pub fn separator_pool(&self) -> String {
+        self.separator_alphabet.chars().filter(|c| !self.exclude_ambiguous || !ambiguous::is_ambiguous(*c)).collect()
+    }
+
+    /// The digits a random digit is picked from, without `0` and `1` if ambiguous characters are excluded.
+    pub fn digit_pool(&self) -> Vec<char> {
+        ('0'..='9').filter(|c| !self.exclude_ambiguous || !ambiguous::is_ambiguous(*c)).collect()
+    }
+
+    /// The pronounceable rules, without ambiguous phonemes if they're excluded.
+    pub fn syllables(&self) -> PronounceableRules {
+        match self.exclude_ambiguous {
+            true => self.pronounceable.without_ambiguous(),
+            false => self.pronounceable.clone(),
+        }
     }
 }
 
@@ -44,13 +340,30 @@ impl Default for Rules {
             words: 2,
             min_length: 5,
             max_length: 7,
-            transform: Box::from("CAPITALISE"),
-            separator_char: Box::from("RANDOM"),
+            transform: Transformation::CAPITALISE,
+            separator_char: Separator::Random,
             separator_alphabet: Box::from("!@$%.&*-+=?:;"),
             match_random_char: true,
             digits_before: 0,
             digits_after: 3,
             amount: 3,
+            wordlist: None,
+            language: Box::from("en"),
+            uniform_words: false,
+            ascii_only: false,
+            transliterate: true,
+            exclude_ambiguous: false,
+            pattern: None,
+            mode: Mode::default(),
+            leet_probability: 0.5,
+            leet_table: [("a", "4"), ("e", "3"), ("i", "1"), ("o", "0"), ("s", "$"), ("t", "7")]
+                .iter()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect(),
+            policy: Policy::default(),
+            chars: CharRules::default(),
+            pin: PinRules::default(),
+            pronounceable: PronounceableRules::default(),
         }
     }
 }
@@ -68,6 +381,102 @@ impl Debug for Rules {
             .field("digits_before", &self.digits_before)
             .field("digits_after", &self.digits_after)
             .field("amount", &self.amount)
+            .field("wordlist", &self.wordlist)
+            .field("language", &self.language)
+            .field("uniform_words", &self.uniform_words)
+            .field("ascii_only", &self.ascii_only)
+            .field("transliterate", &self.transliterate)
+            .field("exclude_ambiguous", &self.exclude_ambiguous)
+            .field("pattern", &self.pattern)
+            .field("mode", &self.mode)
+            .field("leet_probability", &self.leet_probability)
+            .field("leet_table", &self.leet_table)
+            .field("policy", &self.policy)
+            .field("chars", &self.chars)
+            .field("pin", &self.pin)
+            .field("pronounceable", &self.pronounceable)
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words() -> WordList {
+        WordList::from_words(["ox", "bee", "straße", "abenteuerlich", "unabhängigkeit"])
+    }
+
+    #[test]
+    fn word_lengths_are_only_bounded_by_the_word_list() {
+        let rules = Rules {
+            min_length: 2,
+            max_length: 14,
+            ..Rules::default()
+        };
+
+        assert!(rules.validate().is_empty());
+        assert!(rules.validate_words(&words()).is_empty());
+        assert!(Rules { min_length: 13, ..rules.clone() }.validate_words(&words()).is_empty());
+    }
+
+    #[test]
+    fn word_lengths_without_words_suggest_the_lengths_of_the_list() {
+        let rules = Rules {
+            min_length: 7,
+            max_length: 12,
+            ..Rules::default()
+        };
+        let problems = rules.validate_words(&words());
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].field, "min_length");
+        assert!(problems[0].fix.contains("2 to 14 characters"));
+        assert!(Rules { uniform_words: true, ..rules }.validate_words(&words()).is_empty());
+    }
+
+    #[test]
+    fn pattern_length_hints_are_checked_against_the_list() {
+        let rules = Rules {
+            pattern: Some(Box::from("{word:4-5}{sep}{word:13}")),
+            ..Rules::default()
+        };
+        let problems = rules.validate_words(&words());
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].field, "pattern");
+    }
+
+    #[test]
+    fn min_length_must_be_positive_and_at_most_max_length() {
+        let problems = Rules {
+            min_length: 0,
+            ..Rules::default()
+        }
+        .validate();
+        assert_eq!(problems.iter().map(|p| p.field.as_str()).collect::<Vec<_>>(), vec!["min_length"]);
+
+        let problems = Rules {
+            min_length: 8,
+            max_length: 7,
+            ..Rules::default()
+        }
+        .validate();
+        assert_eq!(problems.iter().map(|p| p.field.as_str()).collect::<Vec<_>>(), vec!["min_length"]);
+    }
+
+    #[test]
+    fn every_problem_is_reported_at_once() {
+        let rules = Rules {
+            words: 0,
+            amount: 0,
+            leet_probability: 2.0,
+            language: Box::from("xx"),
+            ..Rules::default()
+        };
+        let fields = rules.validate().into_iter().map(|problem| problem.field).collect::<Vec<_>>();
+
+        assert_eq!(fields, vec!["words", "amount", "language", "leet_probability"]);
+        assert!(matches!(rules.sanity_checks(), Err(RpgenError::Invalid(problems)) if problems.len() == 4));
+    }
+}
@@ -0,0 +1,253 @@
+use serde_json::{json, Value};
+use strum::IntoEnumIterator;
+
+use crate::mode::Mode;
+use crate::wordlist::{BUILTIN, LANGUAGES};
+use crate::Transformation;
+
+/// A JSON Schema describing a config file, so editors can validate and autocomplete it.
+///
+/// The rules can be at the top level or in a `[default]` section with `[profiles.<name>]` sections on top,
+/// and the bounds match those checked by [`Rules::sanity_checks`](crate::Rules::sanity_checks).
+pub fn config_schema() -> Value {
+    let mut schema = rules_schema();
+    let properties = schema["properties"].as_object_mut().unwrap();
+    properties.insert("default".to_string(), json!({ "$ref": "#/$defs/rules", "description": "The rules every profile inherits." }));
+    properties.insert(
+        "profiles".to_string(),
+        json!({
+            "type": "object",
+            "description": "Named rule sets which only need the rules that differ from the defaults.",
+            "additionalProperties": { "$ref": "#/$defs/rules" }
+        }),
+    );
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "rpgen config",
+        "$defs": { "rules": rules_schema() },
+        "type": "object",
+        "properties": schema["properties"],
+        "additionalProperties": false
+    })
+}
+
+/// A JSON Schema describing a single set of [`Rules`](crate::Rules).
+pub fn rules_schema() -> Value {
+    let transformations = Transformation::iter().map(<&str>::from).collect::<Vec<_>>();
+    let modes = Mode::iter().map(|mode| mode.to_string()).collect::<Vec<_>>();
+    let languages = LANGUAGES.iter().map(|(code, _)| *code).collect::<Vec<_>>();
+    let builtin = BUILTIN.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+
+    json!({
+        "type": "object",
+        "properties": {
+            "words": integer("The number of words in each password.", 1, 10),
+            "min_length": { "type": "integer", "description": "The minimum length of each word.", "minimum": 1 },
+            "max_length": { "type": "integer", "description": "The maximum length of each word.", "minimum": 1 },
+            "transform": { "description": "The transformation applied to each word.", "enum": transformations },
+            "separator_char": {
+                "description": "NONE, RANDOM, a single character placed between every word or several characters which are cycled through.",
+                "anyOf": [
+                    { "enum": ["RANDOM", "NONE"] },
+                    { "type": "string", "minLength": 1 },
+                    { "type": "array", "items": { "type": "string", "minLength": 1, "maxLength": 1 } }
+                ]
+            },
+            "separator_alphabet": { "type": "string", "description": "The characters a random separator is picked from." },
+            "match_random_char": boolean("Whether a random separator is reused for every separator within a password."),
+            "digits_before": integer("The number of digits before the words.", 0, 10),
+            "digits_after": integer("The number of digits after the words.", 0, 10),
+            "amount": integer("The number of passwords to generate.", 1, 10000),
+            "wordlist": {
+                "type": "string",
+                "description": "The name of a built-in word list, or a path to a custom word list which is either newline delimited or JSON.",
+                "examples": builtin
+            },
+            "language": { "description": "The language whose embedded word list is used when there is no wordlist.", "enum": languages },
+            "uniform_words": boolean("Whether words are picked uniformly from the whole word list, ignoring the min and max length."),
+            "ascii_only": boolean("Whether passwords are limited to characters which can be typed on any keyboard."),
+            "transliterate": boolean("Whether non-ASCII words are transliterated instead of dropped when ascii_only is set."),
+            "exclude_ambiguous": boolean("Whether characters which are easily mistaken for one another are left out of passwords."),
+            "pattern": { "type": "string", "description": "A pattern like {Word}{sep}{word:4-6}{sep}{d:3}{sym} describing each password." },
+            "mode": { "description": "What kind of password is generated.", "enum": modes },
+            "leet_probability": number("The chance of each character being substituted by LEET_RANDOM.", 0.0, 1.0),
+            "leet_table": {
+                "type": "object",
+                "description": "The substitutions made by the leet transformations, keyed by a single lowercase character.",
+                "propertyNames": { "minLength": 1, "maxLength": 1, "pattern": "^[^A-Z]$" },
+                "additionalProperties": { "type": "string" }
+            },
+            "policy": policy_schema(),
+            "chars": chars_schema(),
+            "pin": pin_schema(),
+            "pronounceable": pronounceable_schema()
+        },
+        "additionalProperties": false
+    })
+}
+
+fn policy_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "The requirements every password must meet.",
+        "properties": {
+            "min_length": { "type": "integer", "description": "The minimum number of characters.", "minimum": 0 },
+            "max_length": { "type": "integer", "description": "The maximum number of characters.", "minimum": 0 },
+            "require_uppercase": boolean("Whether at least one uppercase letter is required."),
+            "require_lowercase": boolean("Whether at least one lowercase letter is required."),
+            "require_digit": boolean("Whether at least one digit is required."),
+            "require_symbol": boolean("Whether at least one symbol is required."),
+            "max_repeating": { "type": "integer", "description": "The most times the same character may repeat in a row.", "minimum": 1 },
+            "max_attempts": { "type": "integer", "description": "How many times a password is regenerated before giving up.", "minimum": 1 }
+        },
+        "additionalProperties": false
+    })
+}
+
+fn chars_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "The rules for random character passwords, used when the mode is chars.",
+        "properties": {
+            "length": integer("The number of characters in each password.", 1, 1024),
+            "lowercase": boolean("Whether lowercase letters are used."),
+            "uppercase": boolean("Whether uppercase letters are used."),
+            "digits": boolean("Whether digits are used."),
+            "symbols": { "type": "string", "description": "The symbols which are used." },
+            "custom": { "type": "string", "description": "Any other characters which are used." },
+            "min_lowercase": { "type": "integer", "description": "The minimum number of lowercase letters.", "minimum": 0 },
+            "min_uppercase": { "type": "integer", "description": "The minimum number of uppercase letters.", "minimum": 0 },
+            "min_digits": { "type": "integer", "description": "The minimum number of digits.", "minimum": 0 },
+            "min_symbols": { "type": "integer", "description": "The minimum number of symbols.", "minimum": 0 },
+            "min_custom": { "type": "integer", "description": "The minimum number of custom characters.", "minimum": 0 }
+        },
+        "additionalProperties": false
+    })
+}
+
+fn pin_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "The rules for numeric PINs, used when the mode is pin.",
+        "properties": {
+            "length": integer("The number of digits in each PIN.", 4, 12),
+            "reject_sequences": boolean("Whether PINs counting up or down by the same step are rejected."),
+            "reject_repeats": boolean("Whether PINs made of a repeated block are rejected."),
+            "reject_palindromes": boolean("Whether PINs which read the same backwards are rejected."),
+            "reject_dates": boolean("Whether PINs which look like a date or year are rejected."),
+            "reject_common": boolean("Whether PINs from the bundled list of most common PINs are rejected.")
+        },
+        "additionalProperties": false
+    })
+}
+
+fn pronounceable_schema() -> Value {
+    json!({
+        "type": "object",
+        "description": "The rules for building words out of syllables, used when the mode is pronounceable.",
+        "properties": {
+            "min_syllables": integer("The minimum number of syllables in each word.", 1, 8),
+            "max_syllables": integer("The maximum number of syllables in each word.", 1, 8),
+            "onsets": phonemes("The consonants which start a syllable."),
+            "vowels": phonemes("The vowels in the middle of a syllable."),
+            "codas": { "type": "array", "description": "The consonants which can end a syllable.", "items": { "type": "string", "pattern": "\\S" } },
+            "coda_probability": number("The chance of a syllable ending in a coda.", 0.0, 1.0)
+        },
+        "additionalProperties": false
+    })
+}
+
+fn integer(description: &str, minimum: usize, maximum: usize) -> Value {
+    json!({ "type": "integer", "description": description, "minimum": minimum, "maximum": maximum })
+}
+
+fn number(description: &str, minimum: f64, maximum: f64) -> Value {
+    json!({ "type": "number", "description": description, "minimum": minimum, "maximum": maximum })
+}
+
+fn boolean(description: &str) -> Value {
+    json!({ "type": "boolean", "description": description })
+}
+
+fn phonemes(description: &str) -> Value {
+    json!({ "type": "array", "description": description, "minItems": 1, "items": { "type": "string", "pattern": "\\S" } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Policy, Rules};
+
+    /// Rules with every optional rule set, so each of them is serialized.
+    fn rules() -> Value {
+        let rules = Rules {
+            wordlist: Some(Box::from("eff-large")),
+            pattern: Some(Box::from("{word}")),
+            policy: Policy {
+                min_length: Some(8),
+                max_length: Some(64),
+                max_repeating: Some(2),
+                ..Policy::default()
+            },
+            ..Rules::default()
+        };
+        serde_json::to_value(rules).unwrap()
+    }
+
+    /// Checks that the tables have the same keys as the schema and that every value is within its bounds.
+    fn matches(schema: &Value, value: &Value, path: &str) {
+        if let (Some(properties), Some(table)) = (schema.get("properties").and_then(Value::as_object), value.as_object()) {
+            let mut expected = properties.keys().collect::<Vec<_>>();
+            let mut actual = table.keys().collect::<Vec<_>>();
+            expected.sort();
+            actual.sort();
+            assert_eq!(expected, actual, "the keys of {} differ", path);
+            table.iter().for_each(|(key, value)| matches(&properties[key], value, &format!("{}.{}", path, key)));
+        }
+
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            assert!(values.contains(value), "{} is {} which isn't in the schema", path, value);
+        }
+        let kind = schema.get("type").and_then(Value::as_str);
+        match kind {
+            Some("integer") => assert!(value.is_u64(), "{} isn't an integer", path),
+            Some("number") => assert!(value.is_number(), "{} isn't a number", path),
+            Some("boolean") => assert!(value.is_boolean(), "{} isn't a boolean", path),
+            Some("string") => assert!(value.is_string(), "{} isn't a string", path),
+            Some("array") => assert!(value.is_array(), "{} isn't an array", path),
+            Some("object") => assert!(value.is_object(), "{} isn't an object", path),
+            _ => {}
+        }
+        if let (Some(minimum), Some(value)) = (schema.get("minimum").and_then(Value::as_f64), value.as_f64()) {
+            assert!(value >= minimum, "{} is below the minimum", path);
+        }
+        if let (Some(maximum), Some(value)) = (schema.get("maximum").and_then(Value::as_f64), value.as_f64()) {
+            assert!(value <= maximum, "{} is above the maximum", path);
+        }
+    }
+
+    #[test]
+    fn schemas_parse_as_json() {
+        for schema in [config_schema(), rules_schema()] {
+            let string = serde_json::to_string_pretty(&schema).unwrap();
+            assert_eq!(serde_json::from_str::<Value>(&string).unwrap(), schema);
+        }
+        assert_eq!(config_schema()["$defs"]["rules"], rules_schema());
+    }
+
+    #[test]
+    fn properties_match_the_rules() {
+        matches(&rules_schema(), &rules(), "rules");
+    }
+
+    #[test]
+    fn unknown_keys_are_disallowed_at_every_level() {
+        let schema = rules_schema();
+        assert_eq!(schema["additionalProperties"], false);
+        assert_eq!(config_schema()["additionalProperties"], false);
+        for table in ["policy", "chars", "pin", "pronounceable"] {
+            assert_eq!(schema["properties"][table]["additionalProperties"], false, "{}", table);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// What is placed between the words of a password.
+///
+/// Written as `NONE`, `RANDOM`, a single character for [`Separator::Fixed`] or several characters for [`Separator::Cycle`],
+/// which can also be a list of single characters.
+/// A cycle is always serialized as a list, so one which spells out `NONE` or `RANDOM` isn't read back as those.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Separator {
+    /// Words are joined without anything between them.
+    None,
+    /// A character picked from the separator alphabet, see [`Rules::match_random_char`](crate::Rules::match_random_char).
+    #[default]
+    Random,
+    /// The same character between every word.
+    Fixed(char),
+    /// Each character in turn, starting over from the first for every password.
+    Cycle(Vec<char>),
+}
+
+impl Separator {
+    /// The characters which can be placed between words, which is nothing for [`Separator::None`] and [`Separator::Random`].
+    pub fn chars(&self) -> &[char] {
+        match self {
+            Separator::None | Separator::Random => &[],
+            Separator::Fixed(char) => std::slice::from_ref(char),
+            Separator::Cycle(chars) => chars,
+        }
+    }
+}
+
+impl FromStr for Separator {
+    type Err = Infallible;
+
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let chars = str.chars().collect::<Vec<_>>();
+        let separator = match chars.as_slice() {
+            _ if str.eq_ignore_ascii_case("NONE") => Separator::None,
+            _ if str.eq_ignore_ascii_case("RANDOM") => Separator::Random,
+            [] => Separator::None,
+            [char] => Separator::Fixed(*char),
+            _ => Separator::Cycle(chars),
+        };
+
+        Ok(separator)
+    }
+}
+
+impl Display for Separator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Separator::None => f.write_str("NONE"),
+            Separator::Random => f.write_str("RANDOM"),
+            Separator::Fixed(_) | Separator::Cycle(_) => f.write_str(&self.chars().iter().collect::<String>()),
+        }
+    }
+}
+
+impl Serialize for Separator {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Separator::Cycle(chars) => serializer.collect_seq(chars.iter().map(char::to_string)),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Separator {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SeparatorVisitor)
+    }
+}
+
+struct SeparatorVisitor;
+
+impl<'de> Visitor<'de> for SeparatorVisitor {
+    type Value = Separator;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("NONE, RANDOM, one or more characters or a list of single characters")
+    }
+
+    fn visit_str<E: Error>(self, str: &str) -> Result<Self::Value, E> {
+        Ok(Separator::from_str(str).unwrap())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut chars = Vec::new();
+        while let Some(item) = seq.next_element::<String>()? {
+            let mut item_chars = item.chars();
+            match (item_chars.next(), item_chars.next()) {
+                (Some(char), None) => chars.push(char),
+                _ => return Err(A::Error::custom(format!("separator list items must be a single character, received {:?}", item))),
+            }
+        }
+
+        Ok(match chars.is_empty() {
+            true => Separator::None,
+            false => Separator::Cycle(chars),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rules;
+
+    fn separators() -> Vec<Separator> {
+        vec![
+            Separator::None,
+            Separator::Random,
+            Separator::Fixed('-'),
+            Separator::Fixed('N'),
+            Separator::Cycle(vec!['-', '_', '.']),
+            Separator::Cycle(vec!['N', 'O', 'N', 'E']),
+            Separator::Cycle(vec!['R', 'A', 'N', 'D', 'O', 'M']),
+            Separator::Cycle(vec!['+']),
+        ]
+    }
+
+    #[test]
+    fn json_round_trips() {
+        for separator in separators() {
+            let json = serde_json::to_string(&separator).unwrap();
+            assert_eq!(serde_json::from_str::<Separator>(&json).unwrap(), separator, "{}", json);
+        }
+    }
+
+    #[test]
+    fn toml_round_trips() {
+        for separator in separators() {
+            let rules = Rules {
+                separator_char: separator.clone(),
+                ..Rules::default()
+            };
+            let toml = toml::to_string(&rules).unwrap();
+            assert_eq!(toml::from_str::<Rules>(&toml).unwrap().separator_char, separator, "{}", toml);
+        }
+    }
+
+    #[test]
+    fn cycles_are_serialized_as_lists() {
+        assert_eq!(serde_json::to_string(&Separator::Cycle(vec!['N', 'O', 'N', 'E'])).unwrap(), r#"["N","O","N","E"]"#);
+        assert_eq!(serde_json::to_string(&Separator::Fixed('-')).unwrap(), r#""-""#);
+        assert_eq!(serde_json::to_string(&Separator::None).unwrap(), r#""NONE""#);
+        assert_eq!(serde_json::to_string(&Separator::Random).unwrap(), r#""RANDOM""#);
+    }
+
+    #[test]
+    fn strings_are_parsed_by_length() {
+        let parse = |json: &str| serde_json::from_str::<Separator>(json).unwrap();
+        assert_eq!(parse(r#""none""#), Separator::None);
+        assert_eq!(parse(r#""""#), Separator::None);
+        assert_eq!(parse(r#""Random""#), Separator::Random);
+        assert_eq!(parse(r#""-""#), Separator::Fixed('-'));
+        assert_eq!(parse(r#""-_""#), Separator::Cycle(vec!['-', '_']));
+        assert_eq!(parse("[]"), Separator::None);
+        assert!(serde_json::from_str::<Separator>(r#"["-_"]"#).is_err());
+    }
+}
@@ -1,12 +1,45 @@
-use strum_macros::{EnumIter, EnumString};
+use std::str::FromStr;
 
-#[allow(non_camel_case_types)]
-#[derive(Debug, EnumIter, EnumString)]
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString, IntoStaticStr};
+
+/// How the case of each word in a password is transformed.
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter, EnumString, IntoStaticStr)]
+#[strum(ascii_case_insensitive)]
 pub enum Transformation {
+    /// Leave words as they appear in the word list.
     NONE,
+    /// Uppercase the first letter of each word.
     CAPITALISE,
+    /// Uppercase every letter except the first of each word.
     ALL_EXCEPT_FIRST,
+    /// Uppercase every letter.
     UPPERCASE,
+    /// Randomly uppercase or lowercase each letter.
     RANDOM,
+    /// Alternate between uppercase and lowercase, starting with uppercase.
     ALTERNATING,
+    /// Substitute every character found in the leet table, like `a` to `4`.
+    LEET,
+    /// Substitute each character found in the leet table with the leet probability.
+    LEET_RANDOM,
+}
+
+impl Serialize for Transformation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.into())
+    }
+}
+
+impl<'de> Deserialize<'de> for Transformation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Transformation::from_str(&name).map_err(|_| {
+            let names = Transformation::iter().map(<&str>::from).collect::<Vec<_>>();
+            D::Error::custom(format!("unknown transformation {}, expected one of {:?}", name, names))
+        })
+    }
 }
@@ -0,0 +1,247 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use rust_embed::EmbeddedFile;
+use serde_json::Value;
+
+use crate::ambiguous;
+use crate::ascii;
+use crate::asset::Asset;
+use crate::error::RpgenError;
+
+/// The names of the word lists embedded into the binary and the asset each one is loaded from.
+pub const BUILTIN: [(&str, &str); 4] = [
+    ("default", "words.json"),
+    ("eff-large", "eff_large_wordlist.txt"),
+    ("eff-short-1", "eff_short_wordlist_1.txt"),
+    ("eff-short-2", "eff_short_wordlist_2_0.txt"),
+];
+
+/// The languages with a word list embedded into the binary, keyed by their ISO 639-1 code, and the asset each one is loaded from.
+pub const LANGUAGES: [(&str, &str); 6] = [
+    ("en", "words.json"),
+    ("de", "words_de.json"),
+    ("es", "words_es.json"),
+    ("fr", "words_fr.json"),
+    ("it", "words_it.json"),
+    ("nl", "words_nl.json"),
+];
+
+/// A list of words bucketed by their length in characters.
+#[derive(Debug, Clone, Default)]
+pub struct WordList {
+    buckets: BTreeMap<usize, Vec<String>>,
+}
+
+impl WordList {
+    /// The default word list embedded into the binary.
+    pub fn embedded() -> Result<WordList, RpgenError> {
+        WordList::builtin("default")
+    }
+
+    /// One of the [`BUILTIN`] word lists embedded into the binary.
+    pub fn builtin(name: &str) -> Result<WordList, RpgenError> {
+        let file = match BUILTIN.iter().find(|(builtin, _)| builtin.eq_ignore_ascii_case(name)) {
+            Some((_, file)) => *file,
+            None => {
+                let names = BUILTIN.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                return Err(RpgenError::WordList(format!("Unknown word list {}, expected one of {:?}", name, names)));
+            }
+        };
+
+        WordList::asset(file)
+    }
+
+    /// The word list embedded into the binary for one of the [`LANGUAGES`].
+    pub fn language(code: &str) -> Result<WordList, RpgenError> {
+        match LANGUAGES.iter().find(|(language, _)| language.eq_ignore_ascii_case(code)) {
+            Some((_, file)) => WordList::asset(file),
+            None => {
+                let codes = LANGUAGES.iter().map(|(code, _)| *code).collect::<Vec<_>>();
+                Err(RpgenError::WordList(format!("Unknown language {}, expected one of {:?}", code, codes)))
+            }
+        }
+    }
+
+    fn asset(file: &str) -> Result<WordList, RpgenError> {
+        let asset: EmbeddedFile = Asset::get(file).ok_or(RpgenError::WordList(format!("Embedded word list {} is missing", file)))?;
+        let str = std::str::from_utf8(asset.data.as_ref())
+            .map_err(|e| RpgenError::WordList(format!("Embedded word list {} isn't valid UTF-8: {}", file, e)))?;
+
+        WordList::parse(str, file.ends_with(".json"))
+    }
+
+    /// Loads a [`BUILTIN`] word list if the name matches one, otherwise loads the word list from the path.
+    pub fn load(name_or_path: &str) -> Result<WordList, RpgenError> {
+        let path = Path::new(name_or_path);
+        match BUILTIN.iter().any(|(name, _)| name.eq_ignore_ascii_case(name_or_path)) {
+            true => WordList::builtin(name_or_path),
+            false if !path.exists() => {
+                let names = BUILTIN.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                Err(RpgenError::WordList(format!("{} is neither a built-in word list {:?} nor an existing file", name_or_path, names)))
+            }
+            false => WordList::from_file(path),
+        }
+    }
+
+    /// Loads a word list from disk.
+    ///
+    /// Files ending in `.json` or starting with `{` are read as a JSON object of length keyed arrays, like the embedded list,
+    /// anything else is read as one word per line, optionally prefixed by diceware rolls like the EFF lists.
+    pub fn from_file(path: &Path) -> Result<WordList, RpgenError> {
+        let content = fs::read_to_string(path).map_err(|e| RpgenError::WordList(format!("Couldn't read word list {}: {}", path.display(), e)))?;
+        let json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) || content.trim_start().starts_with('{');
+
+        parse(&content, json).map_err(|e| RpgenError::WordList(format!("Couldn't parse word list {}: {}", path.display(), e)))
+    }
+
+    /// Parses a word list from either a JSON object of length keyed arrays or newline delimited text.
+    ///
+    /// The keys of a JSON word list are ignored, every word is bucketed by its actual length.
+    pub fn parse(content: &str, json: bool) -> Result<WordList, RpgenError> {
+        parse(content, json).map_err(|e| RpgenError::WordList(format!("Couldn't parse word list: {}", e)))
+    }
+
+    /// Creates a word list from any words, blank entries and duplicates are skipped.
+    pub fn from_words<I, S>(words: I) -> WordList
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut seen = HashSet::new();
+        let mut buckets: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for word in words {
+            let word = word.as_ref().trim();
+            if word.is_empty() || !seen.insert(word.to_string()) {
+                continue;
+            }
+            buckets.entry(word.chars().count()).or_default().push(word.to_string());
+        }
+
+        WordList { buckets }
+    }
+
+    /// A copy of the word list with only ASCII words, the others are transliterated like `müde` to `muede` or dropped.
+    ///
+    /// Transliterated words are bucketed by their new length.
+    pub fn to_ascii(&self, transliterate: bool) -> WordList {
+        WordList::from_words(self.iter().filter_map(|word| match word.is_ascii() {
+            true => Some(word.to_string()),
+            false if transliterate => ascii::transliterate(word),
+            false => None,
+        }))
+    }
+
+    /// A copy of the word list without any words which could contain an [ambiguous](crate::ambiguous::AMBIGUOUS) character in either case.
+    pub fn without_ambiguous(&self) -> WordList {
+        WordList::from_words(self.iter().filter(|word| !ambiguous::could_be_ambiguous(word)))
+    }
+
+    /// The words with exactly this many characters.
+    pub fn bucket(&self, length: usize) -> &[String] {
+        self.buckets.get(&length).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The lengths between min and max inclusive which have at least one word.
+    pub fn lengths(&self, min: usize, max: usize) -> Vec<usize> {
+        self.buckets.range(min..=max).filter(|(_, words)| !words.is_empty()).map(|(length, _)| *length).collect()
+    }
+
+    /// The word at the index when every bucket is laid out in order of length.
+    pub fn get(&self, mut index: usize) -> Option<&str> {
+        for words in self.buckets.values() {
+            match words.get(index) {
+                Some(word) => return Some(word),
+                None => index -= words.len(),
+            }
+        }
+        None
+    }
+
+    /// Every word in order of length.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.buckets.values().flatten().map(String::as_str)
+    }
+
+    /// The total number of words.
+    pub fn len(&self) -> usize {
+        self.buckets.values().map(Vec::len).sum()
+    }
+
+    /// Whether there are no words at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn parse(content: &str, json: bool) -> Result<WordList, String> {
+    if !json {
+        return Ok(WordList::from_words(content.lines().map(strip_dice_roll)));
+    }
+
+    let parsed: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let map = parsed.as_object().ok_or("Expected a JSON object of length keyed arrays")?;
+    let mut words = Vec::new();
+    for (key, value) in map {
+        let array = value.as_array().ok_or(format!("Expected an array of words for key {}", key))?;
+        for word in array {
+            words.push(word.as_str().ok_or(format!("Expected only strings in the array for key {}", key))?);
+        }
+    }
+
+    Ok(WordList::from_words(words))
+}
+
+/// Removes the leading dice roll from a diceware formatted line like `11111\tabacus`.
+fn strip_dice_roll(line: &str) -> &str {
+    let line = line.trim();
+    match line.split_once(char::is_whitespace) {
+        Some((roll, word)) if roll.chars().all(|c| c.is_ascii_digit()) => word.trim(),
+        _ => line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_language_loads() {
+        for (code, _) in LANGUAGES {
+            assert!(WordList::language(code).unwrap().len() > 1000, "{} is missing words", code);
+        }
+    }
+
+    #[test]
+    fn french_words_keep_their_accents() {
+        let words = WordList::language("fr").unwrap();
+        assert!(words.iter().any(|word| word == "été"));
+        assert!(words.iter().filter(|word| !word.is_ascii()).count() > 100);
+
+        let ascii = words.to_ascii(true);
+        assert!(ascii.iter().all(str::is_ascii));
+        assert!(ascii.iter().any(|word| word == "oeuf"));
+        assert!(words.to_ascii(false).len() < words.len());
+    }
+
+    #[test]
+    fn dutch_words_have_no_names() {
+        let words = WordList::language("nl").unwrap();
+        assert!(!words.iter().any(|word| ["hans", "hugo", "anya", "pfeifer"].contains(&word)));
+    }
+
+    #[test]
+    fn words_are_bucketed_by_characters() {
+        let words = WordList::from_words(["straße", "Straße", "ox", "ox", " "]);
+        assert_eq!(words.len(), 3);
+        assert_eq!(words.bucket(6), ["straße", "Straße"]);
+        assert_eq!(words.lengths(1, 10), vec![2, 6]);
+    }
+
+    #[test]
+    fn diceware_rolls_are_stripped() {
+        let words = WordList::parse("11111\tabacus\n11112 abdomen\nplain\n", false).unwrap();
+        assert_eq!(words.iter().collect::<Vec<_>>(), vec!["plain", "abacus", "abdomen"]);
+    }
+}